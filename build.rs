@@ -4,6 +4,12 @@ fn main() {
     // Set app_id as environment variable for compile-time access
     println!("cargo:rustc-env=APP_ID=dev.zidane.screenoff");
 
+    // Any rerun-if-changed replaces Cargo's default of rerunning for every file in the package
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=icons");
+
+    check_locales();
+
     let version = env!("CARGO_PKG_VERSION");
 
     // Generate version.iss for Inno Setup
@@ -30,3 +36,36 @@ fn main() {
             .unwrap();
     }
 }
+
+/// Message keys of a catalog: unindented `key = ...` lines
+fn locale_keys(source: &str) -> std::collections::BTreeSet<String> {
+    source
+        .lines()
+        .filter(|line| !line.starts_with([' ', '#']))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, _)| key.trim().to_string())
+        .collect()
+}
+
+/// Fails the build when a shipped locale is missing a message from English, or has extra ones
+fn check_locales() {
+    println!("cargo:rerun-if-changed=locales");
+    let english = locale_keys(&std::fs::read_to_string("locales/en.ftl").unwrap());
+    for entry in std::fs::read_dir("locales").unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|e| e != "ftl") {
+            continue;
+        }
+        let keys = locale_keys(&std::fs::read_to_string(&path).unwrap());
+        let missing: Vec<_> = english.difference(&keys).collect();
+        let extra: Vec<_> = keys.difference(&english).collect();
+        if !missing.is_empty() || !extra.is_empty() {
+            panic!(
+                "{} is out of sync with en.ftl: missing {:?}, extra {:?}",
+                path.display(),
+                missing,
+                extra
+            );
+        }
+    }
+}
@@ -0,0 +1,104 @@
+# Deutsch
+
+app-name = Screen Off
+
+tooltip-back-on = Wieder an in { $minutes } Min.
+tooltip-off = Aus: { $monitors }
+
+menu-select-monitors = Auszuschaltende Monitore wählen:
+menu-identify = Monitore &identifizieren
+menu-turn-off-selected = &Ausgewählte Bildschirme ausschalten
+menu-turn-back-on = Wieder &einschalten
+menu-turn-off-for = Ausschalten &für
+menu-minutes = { $minutes } Minuten
+menu-turn-on-monitor = { $name } einschalten
+menu-turn-off-monitor = { $name } ausschalten
+menu-start-on-sign-in = Bei Anmeldung &starten
+menu-monitor-details = { $name } ({ $details })
+menu-rename = Monitor &umbenennen
+menu-exit = &Beenden
+
+monitor-fallback-name = Anzeige { $number }
+monitor-mode = { $width }×{ $height } @ { $refresh } Hz
+monitor-primary = Hauptbildschirm
+
+action-toggle = Umschalten
+action-on = Einschalten
+action-off = Ausschalten
+action-toggle-monitor = Anzeige { $number } umschalten
+action-monitor-on = Anzeige { $number } einschalten
+action-monitor-off = Anzeige { $number } ausschalten
+action-timed-off = Für { $minutes } Minuten ausschalten
+action-identify = Monitore identifizieren
+
+schedule-next = Als Nächstes: { $action }, { $time }
+day-mon = Mo
+day-tue = Di
+day-wed = Mi
+day-thu = Do
+day-fri = Fr
+day-sat = Sa
+day-sun = So
+
+config-invalid = Einstellungen konnten nicht gelesen werden und werden nicht gespeichert: { $error }
+
+hotkey-invalid = Ungültiges Tastenkürzel „{ $keys }“: { $error }
+hotkey-duplicate = { $keys } ist mehrfach belegt
+hotkey-in-use = { $keys } wird von einer anderen Anwendung verwendet
+hotkey-error-empty = Tastenkürzel ist leer
+hotkey-error-unknown-key = unbekannte Taste '{ $key }'
+hotkey-error-duplicate-modifier = Zusatztaste '{ $modifier }' ist doppelt
+hotkey-error-missing-key = keine Taste angegeben, nur Zusatztasten
+hotkey-error-multiple-keys = nur eine Taste ist erlaubt, '{ $first }' und '{ $second }' angegeben
+hotkey-error-missing-modifier = '{ $key }' braucht mindestens Strg, Alt, Umschalt oder Win
+
+confirm-prompt =
+    Diese Anzeigeänderungen beibehalten?
+
+    Rückgängig in { $seconds } Sekunden.
+confirm-keep = Ä&nderungen behalten
+confirm-revert = &Rückgängig
+
+rename-prompt = Name für { $name }. Leer lassen, um den eigenen Namen des Monitors zu verwenden.
+rename-ok = OK
+rename-cancel = Abbrechen
+
+recovery-prompt =
+    ScreenOff wurde nicht ordnungsgemäß beendet, während Monitore ausgeschaltet waren.
+
+    Alle Monitore wieder einschalten?
+
+cli-usage =
+    Verwendung: screenoff [Befehl]
+
+    Ohne Befehl wird die Infobereich-Anwendung gestartet.
+
+    Befehle:
+      toggle [N]   Sekundäre Monitore umschalten, oder nur Anzeige N
+      on [N]       Monitore wieder einschalten, oder nur Anzeige N
+      off [N]      Sekundäre Monitore ausschalten, oder nur Anzeige N
+      off --for M  Sekundäre Monitore für M Minuten ausschalten
+      list         Monitore mit ihren Anzeigenummern auflisten
+      identify     Nummer und Namen jedes Monitors darauf anzeigen
+      daemon       Tastenkürzel, Zeitpläne und Richtlinien ohne Infobereich-Symbol ausführen
+      help         Diese Hilfe anzeigen
+cli-missing-minutes = Minuten nach --for fehlen
+cli-not-minutes = '{ $value }' ist keine Minutenanzahl
+cli-not-display = '{ $value }' ist keine Anzeigenummer
+cli-unexpected-argument = unerwartetes Argument '{ $value }'
+cli-unknown-command = unbekannter Befehl '{ $value }'
+cli-off = (aus)
+cli-covered = (abgedeckt)
+
+announce-off = Ausgeschaltete Monitore: { $count }
+announce-all-on = Alle Monitore sind eingeschaltet
+
+notify-off = { $monitors } ausgeschaltet
+notify-on = { $monitors } eingeschaltet
+notify-partial =
+    { $monitors } geändert, aber einige Monitore sind fehlgeschlagen:
+    { $errors }
+notify-error =
+    Die Monitore konnten nicht geändert werden:
+    { $errors }
+notify-undo = Zum Rückgängigmachen klicken.
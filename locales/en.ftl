@@ -0,0 +1,104 @@
+# English, also the fallback for missing keys
+
+app-name = Screen Off
+
+tooltip-back-on = Back on in { $minutes } min
+tooltip-off = Off: { $monitors }
+
+menu-select-monitors = Select Monitors to turn off:
+menu-identify = I&dentify monitors
+menu-turn-off-selected = Turn off &selected screens
+menu-turn-back-on = &Turn back on
+menu-turn-off-for = Turn off &for
+menu-minutes = { $minutes } minutes
+menu-turn-on-monitor = Turn on { $name }
+menu-turn-off-monitor = Turn off { $name }
+menu-start-on-sign-in = Start on Sign &in
+menu-monitor-details = { $name } ({ $details })
+menu-rename = &Rename monitor
+menu-exit = E&xit
+
+monitor-fallback-name = Display { $number }
+monitor-mode = { $width }×{ $height } @ { $refresh } Hz
+monitor-primary = primary
+
+action-toggle = Toggle
+action-on = Turn on
+action-off = Turn off
+action-toggle-monitor = Toggle display { $number }
+action-monitor-on = Turn on display { $number }
+action-monitor-off = Turn off display { $number }
+action-timed-off = Turn off for { $minutes } minutes
+action-identify = Identify monitors
+
+schedule-next = Next: { $action }, { $time }
+day-mon = Mon
+day-tue = Tue
+day-wed = Wed
+day-thu = Thu
+day-fri = Fri
+day-sat = Sat
+day-sun = Sun
+
+config-invalid = Settings could not be read and are not saved: { $error }
+
+hotkey-invalid = Invalid hotkey "{ $keys }": { $error }
+hotkey-duplicate = { $keys } is bound more than once
+hotkey-in-use = { $keys } is in use by another application
+hotkey-error-empty = hotkey is empty
+hotkey-error-unknown-key = unknown key '{ $key }'
+hotkey-error-duplicate-modifier = modifier '{ $modifier }' is repeated
+hotkey-error-missing-key = no key given, only modifiers
+hotkey-error-multiple-keys = only one key is allowed, got '{ $first }' and '{ $second }'
+hotkey-error-missing-modifier = '{ $key }' needs at least one of Ctrl, Alt, Shift or Win
+
+confirm-prompt =
+    Keep these display changes?
+
+    Reverting in { $seconds } seconds.
+confirm-keep = &Keep changes
+confirm-revert = &Revert
+
+rename-prompt = Name for { $name }. Leave empty to use the monitor's own name.
+rename-ok = OK
+rename-cancel = Cancel
+
+recovery-prompt =
+    ScreenOff did not exit cleanly while some monitors were turned off.
+
+    Turn all monitors back on?
+
+cli-usage =
+    Usage: screenoff [command]
+
+    Without a command, starts the tray application.
+
+    Commands:
+      toggle [N]   Toggle the secondary monitors, or only display N
+      on [N]       Turn monitors back on, or only display N
+      off [N]      Turn the secondary monitors off, or only display N
+      off --for M  Turn the secondary monitors off for M minutes
+      list         List monitors with their display numbers
+      identify     Show each monitor's number and name on it
+      daemon       Run hotkeys, schedules and policies without a tray icon
+      help         Show this message
+cli-missing-minutes = missing minutes after --for
+cli-not-minutes = '{ $value }' is not a number of minutes
+cli-not-display = '{ $value }' is not a display number
+cli-unexpected-argument = unexpected argument '{ $value }'
+cli-unknown-command = unknown command '{ $value }'
+cli-off = (off)
+cli-covered = (covered)
+
+announce-off = Monitors turned off: { $count }
+announce-all-on = All monitors are on
+
+notify-off = Turned off { $monitors }
+notify-on = Turned on { $monitors }
+notify-partial =
+    Changed { $monitors }, but some monitors failed:
+    { $errors }
+notify-error =
+    Could not change the monitors:
+    { $errors }
+notify-undo = Click to undo.
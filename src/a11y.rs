@@ -0,0 +1,41 @@
+use windows::Win32::UI::Shell::{NIIF_INFO, NIIF_NOSOUND, NIIF_RESPECT_QUIET_TIME};
+use windows::Win32::UI::WindowsAndMessaging::{
+    SystemParametersInfoW, SPI_GETSCREENREADER, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+};
+
+use crate::config::Config;
+use crate::i18n::{t, tr};
+use crate::tray::show_balloon;
+
+/// Whether a screen reader has told Windows it is running
+pub fn screen_reader_running() -> bool {
+    let mut running = windows::core::BOOL(0);
+    unsafe {
+        SystemParametersInfoW(
+            SPI_GETSCREENREADER,
+            0,
+            Some(&mut running as *mut _ as *mut std::ffi::c_void),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    }
+    .is_ok()
+        && running.as_bool()
+}
+
+/// Tells screen reader users that monitors turned off or on, since they can't see it.
+/// Screen readers read out balloon notifications of the tray icon.
+pub fn announce(config: &Config) {
+    if !screen_reader_running() {
+        return;
+    }
+    let text = if config.saved_modes.is_empty() {
+        t("announce-all-on")
+    } else {
+        tr("announce-off", &[("count", &config.saved_modes.len())])
+    };
+    show_balloon(
+        &t("app-name"),
+        &text,
+        NIIF_INFO | NIIF_NOSOUND | NIIF_RESPECT_QUIET_TIME,
+    );
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::i18n::{t, tr};
+use crate::identify;
+use crate::monitors::{device_name, then, toggle_monitor, toggle_monitors, turn_off, turn_on};
+use crate::timer;
+
+/// Something the user can trigger from a hotkey or the command line.
+/// Monitors are addressed by display number, `2` being `\\.\DISPLAY2`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Toggle,
+    On,
+    Off,
+    ToggleMonitor(u32),
+    MonitorOn(u32),
+    MonitorOff(u32),
+    TimedOff(u32), // minutes
+    Identify,
+}
+
+impl Action {
+    /// Short description for menus and logs
+    pub fn label(&self) -> String {
+        match self {
+            Action::Toggle => t("action-toggle"),
+            Action::On => t("action-on"),
+            Action::Off => t("action-off"),
+            Action::ToggleMonitor(n) => tr("action-toggle-monitor", &[("number", n)]),
+            Action::MonitorOn(n) => tr("action-monitor-on", &[("number", n)]),
+            Action::MonitorOff(n) => tr("action-monitor-off", &[("number", n)]),
+            Action::TimedOff(minutes) => tr("action-timed-off", &[("minutes", minutes)]),
+            Action::Identify => t("action-identify"),
+        }
+    }
+}
+
+pub fn run(action: &Action, config: &mut Config) {
+    match action {
+        Action::Toggle => toggle_monitors(config),
+        Action::On => then(config, |config| {
+            let monitors: Vec<String> = config.saved_modes.keys().cloned().collect();
+            turn_on(config, &monitors);
+        }),
+        // Only the secondaries that are still on, the others stay as they are
+        Action::Off => {
+            let monitors = config.secondary_monitors.clone();
+            turn_off(config, &monitors);
+        }
+        Action::ToggleMonitor(n) => toggle_monitor(config, &device_name(*n)),
+        Action::MonitorOn(n) => turn_on(config, &[device_name(*n)]),
+        Action::MonitorOff(n) => turn_off(config, &[device_name(*n)]),
+        Action::TimedOff(minutes) => timer::start(config, *minutes),
+        Action::Identify => identify::show(config),
+    }
+}
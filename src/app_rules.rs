@@ -0,0 +1,299 @@
+use std::sync::Mutex;
+
+use regex::Regex;
+use windows::core::PWSTR;
+use windows::Win32::Foundation::{CloseHandle, HWND, RECT, STILL_ACTIVE};
+use windows::Win32::Graphics::Gdi::{
+    GetMonitorInfoW, MonitorFromWindow, MONITORINFO, MONITOR_DEFAULTTONULL,
+};
+use windows::Win32::System::Threading::{
+    GetExitCodeProcess, OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+    PROCESS_QUERY_LIMITED_INFORMATION,
+};
+use windows::Win32::UI::Accessibility::{SetWinEventHook, UnhookWinEvent, HWINEVENTHOOK};
+use windows::Win32::UI::WindowsAndMessaging::{
+    GetClassNameW, GetForegroundWindow, GetWindowRect, GetWindowTextW, GetWindowThreadProcessId,
+    SetTimer, EVENT_SYSTEM_FOREGROUND, WINEVENT_OUTOFCONTEXT,
+};
+
+use crate::actions;
+use crate::config::{AppRule, Config, RestorePolicy};
+use crate::monitors::{is_busy, track, turn_on};
+use crate::tray::{CONFIG, TRAY_HWND};
+
+pub const TIMER_ID: usize = 5;
+
+// Catches apps that go fullscreen after gaining focus, and apps that exit
+const TICK_MS: u32 = 2000;
+
+/// What a rule is matched against
+pub struct WindowInfo {
+    pub exe: String,
+    pub class: String,
+    pub title: String,
+    pub fullscreen: bool,
+    pub pid: u32,
+}
+
+struct CompiledRule {
+    rule: AppRule,
+    title: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn matches(&self, window: &WindowInfo) -> bool {
+        let rule = &self.rule;
+        if rule.exe.is_none() && rule.class.is_none() && self.title.is_none() {
+            return false;
+        }
+        rule.exe
+            .as_ref()
+            .is_none_or(|exe| exe.eq_ignore_ascii_case(&window.exe))
+            && rule
+                .class
+                .as_ref()
+                .is_none_or(|class| *class == window.class)
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|title| title.is_match(&window.title))
+            && (!rule.fullscreen || window.fullscreen)
+    }
+}
+
+/// The rule that currently holds monitors off
+struct ActiveRule {
+    restore: RestorePolicy,
+    pid: u32,
+    turned_off: Vec<String>,
+}
+
+struct Engine {
+    rules: Vec<CompiledRule>,
+    active: Option<ActiveRule>,
+    hook: HWINEVENTHOOK,
+}
+
+// The hook handle is only used from the UI thread
+unsafe impl Send for Engine {}
+
+static ENGINE: Mutex<Option<Engine>> = Mutex::new(None);
+
+pub fn start(config: &Config) {
+    let mut rules = Vec::new();
+    for rule in &config.app_rules {
+        let title = match rule.title.as_deref().map(Regex::new).transpose() {
+            Ok(title) => title,
+            Err(e) => {
+                eprintln!("Invalid title pattern in app rule: {}", e);
+                continue;
+            }
+        };
+        rules.push(CompiledRule {
+            rule: rule.clone(),
+            title,
+        });
+    }
+    if rules.is_empty() {
+        return;
+    }
+
+    let hook = unsafe {
+        SetWinEventHook(
+            EVENT_SYSTEM_FOREGROUND,
+            EVENT_SYSTEM_FOREGROUND,
+            None,
+            Some(foreground_changed),
+            0,
+            0,
+            WINEVENT_OUTOFCONTEXT,
+        )
+    };
+    eprintln!("{} app rule(s) active", rules.len());
+    *ENGINE.lock().unwrap() = Some(Engine {
+        rules,
+        active: None,
+        hook,
+    });
+    let hwnd = unsafe { TRAY_HWND };
+    if !hwnd.is_invalid() {
+        unsafe { SetTimer(Some(hwnd), TIMER_ID, TICK_MS, None) };
+    }
+}
+
+pub fn stop() {
+    if let Some(engine) = ENGINE.lock().unwrap().take() {
+        let _ = unsafe { UnhookWinEvent(engine.hook) };
+    }
+}
+
+unsafe extern "system" fn foreground_changed(
+    _hook: HWINEVENTHOOK,
+    _event: u32,
+    _hwnd: HWND,
+    _id_object: i32,
+    _id_child: i32,
+    _event_thread: u32,
+    _event_time: u32,
+) {
+    unsafe {
+        if !CONFIG.is_null() {
+            evaluate(&mut *CONFIG);
+        }
+    }
+}
+
+/// Checks the foreground window against the rules, turning monitors off or back on
+pub fn evaluate(config: &mut Config) {
+    // Checked again by the next tick once the queued changes are done
+    if is_busy() {
+        return;
+    }
+    // Display changes can dispatch the foreground event while a rule is applied
+    let Ok(mut guard) = ENGINE.try_lock() else {
+        return;
+    };
+    let Some(engine) = guard.as_mut() else {
+        return;
+    };
+    let window = foreground_window();
+
+    let mut restore = Vec::new();
+    if let Some(active) = &engine.active {
+        let running = process_running(active.pid);
+        let focused = window.as_ref().is_some_and(|w| w.pid == active.pid);
+        let ended = match active.restore {
+            RestorePolicy::OnExit | RestorePolicy::Never => !running,
+            RestorePolicy::OnFocusLoss => !running || !focused,
+        };
+        if !ended {
+            return;
+        }
+        let active = engine.active.take().unwrap();
+        if active.restore != RestorePolicy::Never {
+            restore = active.turned_off;
+        }
+    }
+
+    let matched = window.and_then(|window| {
+        let rule = engine.rules.iter().find(|r| r.matches(&window))?;
+        eprintln!(
+            "App rule matched {} ({}): {}",
+            window.exe,
+            window.title,
+            rule.rule.action.label()
+        );
+        engine.active = Some(ActiveRule {
+            restore: rule.rule.restore,
+            pid: window.pid,
+            turned_off: Vec::new(),
+        });
+        Some((rule.rule.action.clone(), window.pid))
+    });
+    // Not held while toggling, the rule's monitors are recorded below
+    drop(guard);
+
+    if !restore.is_empty() {
+        eprintln!("App rule ended, turning monitors back on");
+        turn_on(config, &restore);
+    }
+    let Some((action, pid)) = matched else {
+        return;
+    };
+    track(
+        config,
+        |config| actions::run(&action, config),
+        move |config, already_off| {
+            let mut engine = ENGINE.lock().unwrap();
+            let active = engine.as_mut().and_then(|e| e.active.as_mut());
+            if let Some(active) = active.filter(|a| a.pid == pid) {
+                active.turned_off = config
+                    .saved_modes
+                    .keys()
+                    .filter(|m| !already_off.contains(m))
+                    .cloned()
+                    .collect();
+            }
+        },
+    );
+}
+
+fn foreground_window() -> Option<WindowInfo> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.is_invalid() || hwnd == unsafe { TRAY_HWND } {
+        return None;
+    }
+
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(hwnd, Some(&mut pid)) };
+
+    let mut class = [0u16; 256];
+    let len = unsafe { GetClassNameW(hwnd, &mut class) }.max(0) as usize;
+    let class = String::from_utf16_lossy(&class[..len]);
+
+    let mut title = [0u16; 512];
+    let len = unsafe { GetWindowTextW(hwnd, &mut title) }.max(0) as usize;
+    let title = String::from_utf16_lossy(&title[..len]);
+
+    Some(WindowInfo {
+        exe: process_name(pid).unwrap_or_default(),
+        class,
+        title,
+        fullscreen: is_fullscreen(hwnd),
+        pid,
+    })
+}
+
+/// Executable file name of a process, e.g. `vlc.exe`
+fn process_name(pid: u32) -> Option<String> {
+    let process = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }.ok()?;
+    let mut path = [0u16; 1024];
+    let mut len = path.len() as u32;
+    let result = unsafe {
+        QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(path.as_mut_ptr()),
+            &mut len,
+        )
+    };
+    let _ = unsafe { CloseHandle(process) };
+    result.ok()?;
+    let path = String::from_utf16_lossy(&path[..len as usize]);
+    path.rsplit('\\').next().map(str::to_string)
+}
+
+fn process_running(pid: u32) -> bool {
+    let Ok(process) = (unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) })
+    else {
+        return false;
+    };
+    let mut exit_code = 0u32;
+    let result = unsafe { GetExitCodeProcess(process, &mut exit_code) };
+    let _ = unsafe { CloseHandle(process) };
+    result.is_ok() && exit_code == STILL_ACTIVE.0 as u32
+}
+
+/// Whether the window covers its whole monitor
+fn is_fullscreen(hwnd: HWND) -> bool {
+    let mut rect = RECT::default();
+    if unsafe { GetWindowRect(hwnd, &mut rect) }.is_err() {
+        return false;
+    }
+    let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONULL) };
+    if monitor.is_invalid() {
+        return false;
+    }
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    if !unsafe { GetMonitorInfoW(monitor, &mut info) }.as_bool() {
+        return false;
+    }
+    let screen = info.rcMonitor;
+    rect.left <= screen.left
+        && rect.top <= screen.top
+        && rect.right >= screen.right
+        && rect.bottom >= screen.bottom
+}
@@ -0,0 +1,184 @@
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::{LPARAM, WPARAM};
+use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+use windows::Win32::System::DataExchange::COPYDATASTRUCT;
+use windows::Win32::UI::WindowsAndMessaging::{FindWindowW, SendMessageW, WM_COPYDATA};
+
+use crate::actions::{self, Action};
+use crate::config::load_config;
+use crate::i18n::{self, t, tr};
+use crate::identify;
+use crate::monitors::{
+    current_details, display_number, get_connectors, get_monitors, get_primary_monitor,
+    monitor_label, monitor_name, update_secondary_monitors, MonitorDetails,
+};
+use crate::tray::WINDOW_CLASS;
+
+/// `dwData` of a `WM_COPYDATA` message carrying a JSON-encoded action
+pub const COPYDATA_ACTION: usize = 0x5C0F;
+
+pub enum Command {
+    Run(Action),
+    List,
+    Help,
+    Daemon,
+}
+
+/// What `main` does after the command line was handled
+pub enum Startup {
+    Exit(i32),
+    Tray,
+    Headless,
+}
+
+pub fn parse(args: &[String]) -> Result<Option<Command>, String> {
+    let Some(name) = args.first() else {
+        return Ok(None);
+    };
+    if name == "off" && args.get(1).map(String::as_str) == Some("--for") {
+        let minutes = args.get(2).ok_or_else(|| t("cli-missing-minutes"))?;
+        let minutes = minutes
+            .parse::<u32>()
+            .ok()
+            .filter(|m| *m > 0)
+            .ok_or_else(|| tr("cli-not-minutes", &[("value", minutes)]))?;
+        if args.len() > 3 {
+            return Err(tr("cli-unexpected-argument", &[("value", &args[3])]));
+        }
+        return Ok(Some(Command::Run(Action::TimedOff(minutes))));
+    }
+    let display = match args.get(1) {
+        Some(n) => Some(
+            n.parse::<u32>()
+                .map_err(|_| tr("cli-not-display", &[("value", n)]))?,
+        ),
+        None => None,
+    };
+    if args.len() > 2 {
+        return Err(tr("cli-unexpected-argument", &[("value", &args[2])]));
+    }
+    let command = match (name.as_str(), display) {
+        ("toggle", None) => Command::Run(Action::Toggle),
+        ("toggle", Some(n)) => Command::Run(Action::ToggleMonitor(n)),
+        ("on", None) => Command::Run(Action::On),
+        ("on", Some(n)) => Command::Run(Action::MonitorOn(n)),
+        ("off", None) => Command::Run(Action::Off),
+        ("off", Some(n)) => Command::Run(Action::MonitorOff(n)),
+        ("list", None) => Command::List,
+        ("identify", None) => Command::Run(Action::Identify),
+        ("daemon" | "--headless", None) => Command::Daemon,
+        ("help" | "--help" | "-h", None) => Command::Help,
+        _ => return Err(tr("cli-unknown-command", &[("value", &args.join(" "))])),
+    };
+    Ok(Some(command))
+}
+
+/// Runs a command line, or tells `main` which way to start the app
+pub fn run(args: &[String]) -> Startup {
+    if !args.is_empty() {
+        i18n::set_language(load_config().language.as_deref());
+    }
+    let command = parse(args);
+    if !matches!(command, Ok(None) | Ok(Some(Command::Daemon))) {
+        // Release builds have no console of their own
+        let _ = unsafe { AttachConsole(ATTACH_PARENT_PROCESS) };
+    }
+    match command {
+        Ok(None) => Startup::Tray,
+        Ok(Some(Command::Daemon)) => Startup::Headless,
+        Ok(Some(Command::Run(action))) => Startup::Exit(run_action(&action)),
+        Ok(Some(Command::List)) => {
+            list();
+            Startup::Exit(0)
+        }
+        Ok(Some(Command::Help)) => {
+            println!("{}", t("cli-usage"));
+            Startup::Exit(0)
+        }
+        Err(e) => {
+            eprintln!("screenoff: {}\n\n{}", e, t("cli-usage"));
+            Startup::Exit(2)
+        }
+    }
+}
+
+/// Hands the action to the running tray instance, or runs it here if there is none
+fn run_action(action: &Action) -> i32 {
+    let class_name: Vec<u16> = WINDOW_CLASS
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    if let Ok(hwnd) = unsafe { FindWindowW(PCWSTR(class_name.as_ptr()), PCWSTR::null()) } {
+        let data = serde_json::to_vec(action).unwrap();
+        let copy_data = COPYDATASTRUCT {
+            dwData: COPYDATA_ACTION,
+            cbData: data.len() as u32,
+            lpData: data.as_ptr() as *mut std::ffi::c_void,
+        };
+        let handled = unsafe {
+            SendMessageW(
+                hwnd,
+                WM_COPYDATA,
+                Some(WPARAM(0)),
+                Some(LPARAM(&copy_data as *const _ as isize)),
+            )
+        };
+        return if handled.0 != 0 { 0 } else { 1 };
+    }
+
+    let mut config = load_config();
+    if config.saved_modes.is_empty() && config.secondary_monitors.is_empty() {
+        update_secondary_monitors(&mut config);
+    }
+    actions::run(action, &mut config);
+    if *action == Action::Identify {
+        identify::wait();
+    }
+    0
+}
+
+fn list() {
+    let config = load_config();
+    let primary = get_primary_monitor();
+    let connectors = get_connectors();
+    for (monitor, friendly_name) in get_monitors() {
+        // Covered monitors stay attached, they are listed with the off ones
+        if config.overlay_monitors.contains(&monitor) {
+            continue;
+        }
+        let number = display_number(&monitor).unwrap_or_default();
+        let name = monitor_name(&config, &monitor, &friendly_name);
+        match current_details(&monitor) {
+            Some(details) => {
+                let details = MonitorDetails {
+                    connector: connectors.get(&monitor).cloned(),
+                    ..details
+                };
+                let is_primary = Some(&monitor) == primary.as_ref();
+                println!(
+                    "{:>3}  {}  ({})",
+                    number,
+                    name,
+                    details.describe(is_primary)
+                );
+            }
+            None => println!("{:>3}  {}", number, name),
+        }
+    }
+    let mut off: Vec<&String> = config.saved_modes.keys().collect();
+    off.sort();
+    for monitor in off {
+        let number = display_number(monitor).unwrap_or_default();
+        let state = if config.overlay_monitors.contains(monitor) {
+            t("cli-covered")
+        } else {
+            t("cli-off")
+        };
+        println!(
+            "{:>3}  {} {}",
+            number,
+            monitor_label(&config, monitor),
+            state
+        );
+    }
+}
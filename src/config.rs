@@ -2,11 +2,339 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Mutex;
 
-#[derive(Serialize, Deserialize, Default)]
+use crate::actions::Action;
+use crate::recovery::DEFAULT_RESTORE_HOTKEY;
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub secondary_monitors: Vec<String>,
     pub saved_modes: HashMap<String, (u32, u32, i32, i32)>, // width, height, x, y
+    pub overlay_monitors: Vec<String>,                      // covered rather than disabled
+    pub monitor_names: HashMap<String, String>,             // device name -> friendly name
+    pub aliases: HashMap<String, String>,                   // device name -> name given by the user
+    pub timer_until: Option<u64>, // unix time when timed-off monitors come back on
+    pub timer_monitors: Vec<String>, // monitors the timer turned off
+    pub hooks: Hooks,
+    pub hotkeys: Vec<HotkeyBinding>,
+    pub sequence_timeout_ms: u32,
+    pub idle: IdlePolicy,
+    pub schedules: Vec<ScheduleEntry>,
+    pub power: PowerPolicy,
+    pub lock: LockPolicy,
+    pub app_rules: Vec<AppRule>,
+    pub confirm: ConfirmPolicy,
+    pub on_exit: ExitPolicy,
+    pub restore_hotkey: String,
+    pub running: bool,  // still set at startup after a crash
+    pub headless: bool, // run without a tray icon, like `screenoff daemon`
+    pub theme: ThemeConfig,
+    pub language: Option<String>, // e.g. "de", the Windows language if not set
+    pub notifications: Notifications,
+    pub clicks: ClickActions,
+    pub off_mode: OffMode,
+    pub block_cursor: bool, // keep the cursor off covered monitors
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            secondary_monitors: Vec::new(),
+            saved_modes: HashMap::new(),
+            overlay_monitors: Vec::new(),
+            monitor_names: HashMap::new(),
+            aliases: HashMap::new(),
+            timer_until: None,
+            timer_monitors: Vec::new(),
+            hooks: Hooks::default(),
+            hotkeys: vec![HotkeyBinding {
+                keys: "Ctrl+Alt+T".to_string(),
+                action: Action::Toggle,
+            }],
+            sequence_timeout_ms: 1500,
+            idle: IdlePolicy::default(),
+            schedules: Vec::new(),
+            power: PowerPolicy::default(),
+            lock: LockPolicy::default(),
+            app_rules: Vec::new(),
+            confirm: ConfirmPolicy::default(),
+            on_exit: ExitPolicy::KeepOff,
+            restore_hotkey: DEFAULT_RESTORE_HOTKEY.to_string(),
+            running: false,
+            headless: false,
+            theme: ThemeConfig::default(),
+            language: None,
+            notifications: Notifications::default(),
+            clicks: ClickActions::default(),
+            off_mode: OffMode::Disable,
+            block_cursor: false,
+        }
+    }
+}
+
+/// A global hotkey, e.g. `{ "keys": "Ctrl+Shift+F12", "action": "off" }`.
+/// `keys` may also be a sequence such as `"Ctrl+Alt+T, 2"`.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HotkeyBinding {
+    pub keys: String,
+    pub action: Action,
+}
+
+/// Commands run around a toggle. Each one is optional.
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct Hooks {
+    pub before_off: Option<Hook>,
+    pub after_off: Option<Hook>,
+    pub before_on: Option<Hook>,
+    pub after_on: Option<Hook>,
+    pub on_error: Option<Hook>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Hook {
+    pub command: String,
+    pub timeout_secs: u64,
+    /// Only meaningful for `before_*` hooks: a failing hook cancels the toggle
+    pub veto: bool,
+}
+
+impl Default for Hook {
+    fn default() -> Self {
+        Hook {
+            command: String::new(),
+            timeout_secs: 10,
+            veto: false,
+        }
+    }
+}
+
+/// Turns the secondary monitors off after a while without keyboard or mouse input
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct IdlePolicy {
+    pub enabled: bool,
+    pub minutes: u32,
+    /// Turn the monitors back on at the next input, otherwise they stay off
+    pub restore_on_input: bool,
+}
+
+impl Default for IdlePolicy {
+    fn default() -> Self {
+        IdlePolicy {
+            enabled: false,
+            minutes: 10,
+            restore_on_input: true,
+        }
+    }
+}
+
+/// A weekly rule, e.g. `{ "days": "mon-fri", "at": "18:00", "action": "off" }`
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ScheduleEntry {
+    pub days: String,
+    pub at: String,
+    pub action: Action,
+}
+
+/// Turns monitors off while running on battery
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct PowerPolicy {
+    pub enabled: bool,
+    /// Device names to turn off, the secondary monitors if empty
+    pub monitors: Vec<String>,
+    pub restore_on_ac: bool,
+}
+
+impl Default for PowerPolicy {
+    fn default() -> Self {
+        PowerPolicy {
+            enabled: false,
+            monitors: Vec::new(),
+            restore_on_ac: true,
+        }
+    }
+}
+
+/// Turns every non-primary monitor off while the workstation is locked
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LockPolicy {
+    pub off_on_lock: bool,
+    pub restore_on_unlock: bool,
+}
+
+impl Default for LockPolicy {
+    fn default() -> Self {
+        LockPolicy {
+            off_on_lock: false,
+            restore_on_unlock: true,
+        }
+    }
+}
+
+/// Runs an action while a matching app is in the foreground.
+/// All given criteria must match; `title` is a regular expression.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AppRule {
+    pub exe: Option<String>,
+    pub class: Option<String>,
+    pub title: Option<String>,
+    /// Only match while the window covers its whole monitor
+    pub fullscreen: bool,
+    pub action: Action,
+    pub restore: RestorePolicy,
+}
+
+impl Default for AppRule {
+    fn default() -> Self {
+        AppRule {
+            exe: None,
+            class: None,
+            title: None,
+            fullscreen: false,
+            action: Action::Off,
+            restore: RestorePolicy::OnExit,
+        }
+    }
+}
+
+/// When monitors turned off by an app rule come back on
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RestorePolicy {
+    OnExit,
+    OnFocusLoss,
+    Never,
+}
+
+/// Asks to keep a change made from the tray, a hotkey or the command line,
+/// reverting it when not confirmed in time
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ConfirmPolicy {
+    pub enabled: bool,
+    pub seconds: u32,
+}
+
+impl Default for ConfirmPolicy {
+    fn default() -> Self {
+        ConfirmPolicy {
+            enabled: false,
+            seconds: 15,
+        }
+    }
+}
+
+/// What happens to monitors that are off when the app exits
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitPolicy {
+    /// On quit, logoff and shutdown
+    RestoreOnQuit,
+    /// Only on logoff and shutdown
+    RestoreOnSessionEnd,
+    KeepOff,
+}
+
+/// Tray menu colors. Each color is `#RRGGBB` and replaces the one from `mode`;
+/// high-contrast mode always uses the system colors.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ThemeConfig {
+    pub mode: ThemeMode,
+    pub background: Option<String>,
+    pub highlight: Option<String>,
+    pub text: Option<String>,
+    pub highlight_text: Option<String>,
+    pub disabled_text: Option<String>,
+    pub separator: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeMode {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// Which outcomes of turning monitors off or on show a notification
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Notifications {
+    pub success: bool,
+    pub partial_failure: bool,
+    pub error: bool,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Notifications {
+            success: false,
+            partial_failure: true,
+            error: true,
+        }
+    }
+}
+
+/// What clicking the tray icon does. Right click always opens the menu.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ClickActions {
+    pub single: ClickAction,
+    pub double: ClickAction,
+    pub middle: ClickAction,
+    pub shift: ClickAction, // Shift + left click
+}
+
+impl Default for ClickActions {
+    fn default() -> Self {
+        ClickActions {
+            single: ClickAction::Tray(TrayAction::None),
+            double: ClickAction::Action(Action::Toggle),
+            middle: ClickAction::Tray(TrayAction::None),
+            shift: ClickAction::Tray(TrayAction::None),
+        }
+    }
+}
+
+/// A tray-only action or any hotkey action, e.g. `"menu"` or `{ "timed_off": 30 }`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum ClickAction {
+    Tray(TrayAction),
+    Action(Action),
+}
+
+impl ClickAction {
+    pub fn is_none(&self) -> bool {
+        *self == ClickAction::Tray(TrayAction::None)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayAction {
+    None,
+    Menu,
+    RestoreAll,
+}
+
+/// How monitors are turned off
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OffMode {
+    /// Detach the display, Windows moves its windows to the remaining monitors
+    Disable,
+    /// Cover it with a black window, leaving windows where they are
+    Overlay,
 }
 
 fn get_config_path() -> PathBuf {
@@ -22,17 +350,48 @@ fn get_config_path() -> PathBuf {
     config_dir.join("config.json")
 }
 
+/// Why the config file could not be read. It is then left as it is rather than replaced.
+pub static CONFIG_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
 pub fn load_config() -> Config {
     let path = get_config_path();
-    if path.exists() {
-        let data = fs::read_to_string(path).unwrap_or_default();
-        serde_json::from_str(&data).unwrap_or_default()
-    } else {
-        Config::default()
+    if !path.exists() {
+        return Config::default();
+    }
+    let data = match fs::read_to_string(&path) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+            *CONFIG_ERROR.lock().unwrap() = Some(e.to_string());
+            return Config::default();
+        }
+    };
+    match serde_json::from_str(&data) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!(
+                "Invalid config {}: {}, using defaults without saving over it",
+                path.display(),
+                e
+            );
+            *CONFIG_ERROR.lock().unwrap() = Some(e.to_string());
+            let mut config = Config::default();
+            // Monitors that are off can still be restored if that part is readable
+            if let Some(saved_modes) = serde_json::from_str::<serde_json::Value>(&data)
+                .ok()
+                .and_then(|value| serde_json::from_value(value.get("saved_modes")?.clone()).ok())
+            {
+                config.saved_modes = saved_modes;
+            }
+            config
+        }
     }
 }
 
 pub fn save_config(config: &Config) {
+    if CONFIG_ERROR.lock().unwrap().is_some() {
+        return;
+    }
     let path = get_config_path();
     let data = serde_json::to_string(config).unwrap();
     fs::write(path, data).unwrap();
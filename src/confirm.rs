@@ -0,0 +1,259 @@
+use std::sync::{Mutex, Once};
+
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::Graphics::Gdi::{GetStockObject, DEFAULT_GUI_FONT};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, GetDlgItem, GetSystemMetrics, KillTimer,
+    RegisterClassW, SendMessageW, SetForegroundWindow, SetTimer, SetWindowTextW, BS_DEFPUSHBUTTON,
+    BS_PUSHBUTTON, HMENU, SM_CXSCREEN, SM_CYSCREEN, WINDOW_STYLE, WM_CLOSE, WM_COMMAND, WM_SETFONT,
+    WM_TIMER, WNDCLASSW, WS_CAPTION, WS_CHILD, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP,
+    WS_SYSMENU, WS_TABSTOP, WS_VISIBLE,
+};
+
+use crate::config::Config;
+use crate::i18n::{t, tr};
+use crate::monitors::{then, track, turn_off, turn_on};
+use crate::tray::{CONFIG, HINSTANCE};
+
+const WINDOW_CLASS: PCWSTR = w!("ScreenOffConfirmClass");
+
+const TEXT_ID: i32 = 100;
+const KEEP_ID: usize = 1;
+const REVERT_ID: usize = 2;
+const COUNTDOWN_TIMER_ID: usize = 1;
+
+const WIDTH: i32 = 360;
+const HEIGHT: i32 = 150;
+
+/// An open prompt and the state to go back to
+struct Prompt {
+    hwnd: HWND,
+    before: Vec<String>, // monitors that were off before the change
+    remaining: u32,      // seconds
+}
+
+// The window handle is only used from the UI thread
+unsafe impl Send for Prompt {}
+
+static PROMPT: Mutex<Option<Prompt>> = Mutex::new(None);
+
+static REGISTER_CLASS: Once = Once::new();
+
+fn off_monitors(config: &Config) -> Vec<String> {
+    let mut monitors: Vec<String> = config.saved_modes.keys().cloned().collect();
+    monitors.sort();
+    monitors
+}
+
+/// Runs a user-initiated change, then asks whether to keep it when confirmation is enabled
+pub fn apply(config: &mut Config, change: impl FnOnce(&mut Config)) {
+    if !config.confirm.enabled {
+        change(config);
+        return;
+    }
+    // Asked once the change is done, which may wait for a before hook
+    track(config, change, |config, mut before| {
+        before.sort();
+        ask(config, before)
+    });
+}
+
+fn ask(config: &mut Config, before: Vec<String>) {
+    if off_monitors(config) == before {
+        return;
+    }
+
+    let seconds = config.confirm.seconds.max(1);
+    let mut prompt = PROMPT.lock().unwrap();
+    if let Some(open) = prompt.as_mut() {
+        if off_monitors(config) == open.before {
+            // Changed back by hand, nothing left to confirm
+            drop(prompt);
+            finish(true);
+            return;
+        }
+        // Another change while asking: keep the original state to revert to
+        open.remaining = seconds;
+        update_text(open);
+        return;
+    }
+    let Some(hwnd) = create_window() else {
+        return;
+    };
+    let new_prompt = Prompt {
+        hwnd,
+        before,
+        remaining: seconds,
+    };
+    update_text(&new_prompt);
+    *prompt = Some(new_prompt);
+    unsafe { SetTimer(Some(hwnd), COUNTDOWN_TIMER_ID, 1000, None) };
+}
+
+fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn update_text(prompt: &Prompt) {
+    let text = wide(&tr("confirm-prompt", &[("seconds", &prompt.remaining)]));
+    if let Ok(label) = unsafe { GetDlgItem(Some(prompt.hwnd), TEXT_ID) } {
+        let _ = unsafe { SetWindowTextW(label, PCWSTR(text.as_ptr())) };
+    }
+}
+
+fn create_window() -> Option<HWND> {
+    let hinstance = unsafe { HINSTANCE };
+    REGISTER_CLASS.call_once(|| {
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: hinstance,
+            lpszClassName: WINDOW_CLASS,
+            ..Default::default()
+        };
+        unsafe { RegisterClassW(&class) };
+    });
+
+    let title = wide(&t("app-name"));
+
+    // Centered on the primary monitor, which is never turned off
+    let x = (unsafe { GetSystemMetrics(SM_CXSCREEN) } - WIDTH) / 2;
+    let y = (unsafe { GetSystemMetrics(SM_CYSCREEN) } - HEIGHT) / 2;
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
+            WINDOW_CLASS,
+            PCWSTR(title.as_ptr()),
+            WS_POPUP | WS_CAPTION | WS_SYSMENU | WS_VISIBLE,
+            x,
+            y,
+            WIDTH,
+            HEIGHT,
+            None,
+            None,
+            Some(hinstance),
+            None,
+        )
+    }
+    .ok()?;
+
+    let controls = [
+        (
+            w!("STATIC"),
+            wide(""),
+            WINDOW_STYLE(0),
+            TEXT_ID as usize,
+            (16, 12, 320, 50),
+        ),
+        (
+            w!("BUTTON"),
+            wide(&t("confirm-keep")),
+            WINDOW_STYLE(BS_DEFPUSHBUTTON as u32) | WS_TABSTOP,
+            KEEP_ID,
+            (96, 72, 116, 28),
+        ),
+        (
+            w!("BUTTON"),
+            wide(&t("confirm-revert")),
+            WINDOW_STYLE(BS_PUSHBUTTON as u32) | WS_TABSTOP,
+            REVERT_ID,
+            (220, 72, 116, 28),
+        ),
+    ];
+    let font = unsafe { GetStockObject(DEFAULT_GUI_FONT) };
+    for (class, text, style, id, (x, y, width, height)) in controls {
+        if let Ok(control) = unsafe {
+            CreateWindowExW(
+                Default::default(),
+                class,
+                PCWSTR(text.as_ptr()),
+                WS_CHILD | WS_VISIBLE | style,
+                x,
+                y,
+                width,
+                height,
+                Some(hwnd),
+                Some(HMENU(id as *mut _)),
+                Some(hinstance),
+                None,
+            )
+        } {
+            unsafe { SendMessageW(control, WM_SETFONT, Some(WPARAM(font.0 as usize)), None) };
+        }
+    }
+    let _ = unsafe { SetForegroundWindow(hwnd) };
+    Some(hwnd)
+}
+
+/// Closes the prompt, going back to the state from before the change unless kept
+fn finish(keep: bool) {
+    let Some(prompt) = PROMPT.lock().unwrap().take() else {
+        return;
+    };
+    let _ = unsafe { KillTimer(Some(prompt.hwnd), COUNTDOWN_TIMER_ID) };
+    let _ = unsafe { DestroyWindow(prompt.hwnd) };
+    if keep || unsafe { CONFIG.is_null() } {
+        return;
+    }
+
+    // Reverted after the changes still waiting for a hook
+    then(unsafe { &mut *CONFIG }, move |config| {
+        revert(config, prompt.before)
+    });
+}
+
+fn revert(config: &mut Config, before: Vec<String>) {
+    let after = off_monitors(config);
+    let to_turn_on: Vec<String> = after
+        .iter()
+        .filter(|m| !before.contains(m))
+        .cloned()
+        .collect();
+    let to_turn_off: Vec<String> = before
+        .iter()
+        .filter(|m| !after.contains(m))
+        .cloned()
+        .collect();
+    eprintln!("Display changes not confirmed, reverting");
+    if !to_turn_on.is_empty() {
+        turn_on(config, &to_turn_on);
+    }
+    if !to_turn_off.is_empty() {
+        turn_off(config, &to_turn_off);
+    }
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => match wparam.0 & 0xFFFF {
+            KEEP_ID => finish(true),
+            REVERT_ID => finish(false),
+            _ => {}
+        },
+        WM_TIMER if wparam.0 == COUNTDOWN_TIMER_ID => {
+            let expired = {
+                let mut prompt = PROMPT.lock().unwrap();
+                match prompt.as_mut() {
+                    Some(prompt) => {
+                        prompt.remaining = prompt.remaining.saturating_sub(1);
+                        update_text(prompt);
+                        prompt.remaining == 0
+                    }
+                    None => false,
+                }
+            };
+            if expired {
+                finish(false);
+            }
+        }
+        // Closing the window counts as not confirming
+        WM_CLOSE => finish(false),
+        _ => return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+    LRESULT(0)
+}
@@ -0,0 +1,243 @@
+use std::os::windows::process::CommandExt;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+use windows::Win32::UI::WindowsAndMessaging::{PostMessageW, WM_APP};
+
+use crate::config::Hook;
+
+/// Posted to the tray window when a `before_*` hook started with `start` has exited.
+/// `wParam` is the id passed to `start`, `lParam` is 1 when the change may go ahead.
+pub const WM_HOOK_DONE: u32 = WM_APP + 1;
+
+#[derive(Clone, Copy)]
+pub enum HookEvent {
+    BeforeOff,
+    AfterOff,
+    BeforeOn,
+    AfterOn,
+    Error,
+}
+
+impl HookEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::BeforeOff => "before_off",
+            HookEvent::AfterOff => "after_off",
+            HookEvent::BeforeOn => "before_on",
+            HookEvent::AfterOn => "after_on",
+            HookEvent::Error => "on_error",
+        }
+    }
+
+    /// The monitor state the toggle leads to
+    fn state(self, screens_off: bool) -> &'static str {
+        match self {
+            HookEvent::BeforeOff | HookEvent::AfterOff => "off",
+            HookEvent::BeforeOn | HookEvent::AfterOn => "on",
+            HookEvent::Error if screens_off => "off",
+            HookEvent::Error => "on",
+        }
+    }
+
+    fn vetoes(self) -> bool {
+        matches!(self, HookEvent::BeforeOff | HookEvent::BeforeOn)
+    }
+}
+
+pub fn is_set(hook: &Option<Hook>) -> bool {
+    hook.as_ref()
+        .is_some_and(|hook| !hook.command.trim().is_empty())
+}
+
+/// Runs a hook command. `before_*` hooks are waited for up to their timeout, the others
+/// run in the background. Returns `false` only when a vetoing hook failed, meaning the
+/// toggle should be cancelled.
+pub fn run(
+    hook: &Option<Hook>,
+    event: HookEvent,
+    monitors: &[String],
+    screens_off: bool,
+    error: Option<&str>,
+) -> bool {
+    let Some(hook) = hook.as_ref().filter(|_| is_set(hook)) else {
+        return true;
+    };
+
+    eprintln!("Running {} hook: {}", event.name(), hook.command);
+    if event.vetoes() {
+        return execute(hook, event, monitors, screens_off, error);
+    }
+    let hook = hook.clone();
+    let monitors = monitors.to_vec();
+    let error = error.map(str::to_string);
+    std::thread::spawn(move || execute(&hook, event, &monitors, screens_off, error.as_deref()));
+    true
+}
+
+/// Runs a `before_*` hook on a worker thread, so the message loop keeps running, and
+/// posts `WM_HOOK_DONE` with `id` to `hwnd` once it has exited
+pub fn start(
+    hook: &Option<Hook>,
+    event: HookEvent,
+    monitors: &[String],
+    screens_off: bool,
+    hwnd: HWND,
+    id: usize,
+) {
+    let Some(hook) = hook.clone().filter(|_| is_set(hook)) else {
+        return;
+    };
+    eprintln!("Running {} hook: {}", event.name(), hook.command);
+    let monitors = monitors.to_vec();
+    // Window handles can be posted to from any thread
+    let hwnd = hwnd.0 as isize;
+    std::thread::spawn(move || {
+        let allowed = execute(&hook, event, &monitors, screens_off, None);
+        let hwnd = HWND(hwnd as *mut _);
+        let _ = unsafe {
+            PostMessageW(
+                Some(hwnd),
+                WM_HOOK_DONE,
+                WPARAM(id),
+                LPARAM(allowed as isize),
+            )
+        };
+    });
+}
+
+/// Runs the hook and waits for it, false when it failed and may veto the change
+fn execute(
+    hook: &Hook,
+    event: HookEvent,
+    monitors: &[String],
+    screens_off: bool,
+    error: Option<&str>,
+) -> bool {
+    let result = spawn(hook, event, monitors, screens_off, error)
+        .and_then(|mut child| wait_for(&mut child, hook.timeout_secs));
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            eprintln!("{} hook failed: {}", event.name(), e);
+            !(event.vetoes() && hook.veto)
+        }
+    }
+}
+
+fn spawn(
+    hook: &Hook,
+    event: HookEvent,
+    monitors: &[String],
+    screens_off: bool,
+    error: Option<&str>,
+) -> Result<Child, String> {
+    Command::new("cmd")
+        .arg("/C")
+        .raw_arg(&hook.command)
+        .env("SCREENOFF_EVENT", event.name())
+        .env("SCREENOFF_MONITORS", monitors.join(";"))
+        .env("SCREENOFF_STATE", event.state(screens_off))
+        .env("SCREENOFF_ERROR", error.unwrap_or_default())
+        .stdin(Stdio::null())
+        .creation_flags(CREATE_NO_WINDOW.0)
+        .spawn()
+        .map_err(|e| format!("failed to start: {}", e))
+}
+
+/// Waits for the hook to exit, killing it after `timeout_secs`
+fn wait_for(child: &mut Child, timeout_secs: u64) -> Result<(), String> {
+    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+    loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            return exit_result(status);
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("timed out after {}s", timeout_secs));
+        }
+        std::thread::sleep(remaining.min(Duration::from_millis(50)));
+    }
+}
+
+fn exit_result(status: ExitStatus) -> Result<(), String> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("exited with {}", status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(command: &str, timeout_secs: u64) -> Option<Hook> {
+        Some(Hook {
+            command: command.to_string(),
+            timeout_secs,
+            veto: true,
+        })
+    }
+
+    #[test]
+    fn successful_hook_allows_the_toggle() {
+        let monitors = ["\\\\.\\DISPLAY2".to_string()];
+        assert!(run(
+            &hook("exit 0", 5),
+            HookEvent::BeforeOff,
+            &monitors,
+            false,
+            None
+        ));
+    }
+
+    #[test]
+    fn failing_hook_vetoes_the_toggle() {
+        let monitors = ["\\\\.\\DISPLAY2".to_string()];
+        assert!(!run(
+            &hook("exit 3", 5),
+            HookEvent::BeforeOff,
+            &monitors,
+            false,
+            None
+        ));
+    }
+
+    #[test]
+    fn failing_hook_without_veto_allows_the_toggle() {
+        let mut hook = hook("exit 3", 5);
+        hook.as_mut().unwrap().veto = false;
+        assert!(run(&hook, HookEvent::BeforeOn, &[], true, None));
+    }
+
+    #[test]
+    fn overrunning_hook_is_killed_and_vetoes() {
+        let start = Instant::now();
+        assert!(!run(
+            &hook("ping -n 30 127.0.0.1 >nul", 1),
+            HookEvent::BeforeOn,
+            &[],
+            true,
+            None
+        ));
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn after_hooks_do_not_block() {
+        let start = Instant::now();
+        assert!(run(
+            &hook("ping -n 30 127.0.0.1 >nul", 1),
+            HookEvent::AfterOff,
+            &[],
+            true,
+            None
+        ));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+}
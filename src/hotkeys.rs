@@ -0,0 +1,470 @@
+use std::fmt;
+use std::sync::Mutex;
+
+use windows::Win32::Foundation::{HWND, LPARAM};
+use windows::Win32::UI::Input::KeyboardAndMouse::{
+    RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_NOREPEAT,
+};
+use windows::Win32::UI::WindowsAndMessaging::{KillTimer, SetTimer};
+
+use crate::actions::Action;
+use crate::config::Config;
+use crate::i18n::{t, tr};
+use crate::sequences::{SequenceMatcher, Step};
+
+// Same values as the Win32 MOD_* flags
+pub const MOD_ALT: u32 = 0x1;
+pub const MOD_CONTROL: u32 = 0x2;
+pub const MOD_SHIFT: u32 = 0x4;
+pub const MOD_WIN: u32 = 0x8;
+
+/// Hotkeys that could not be parsed or registered, shown in the tray
+pub static FAILED_HOTKEYS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// A key combination such as `Ctrl+Shift+F12`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub modifiers: u32,
+    pub key: u32, // virtual-key code
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HotkeyError {
+    Empty,
+    UnknownKey(String),
+    DuplicateModifier(String),
+    MissingKey,
+    MultipleKeys(String, String),
+    MissingModifier(String),
+}
+
+impl fmt::Display for HotkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HotkeyError::Empty => write!(f, "{}", t("hotkey-error-empty")),
+            HotkeyError::UnknownKey(k) => {
+                write!(f, "{}", tr("hotkey-error-unknown-key", &[("key", k)]))
+            }
+            HotkeyError::DuplicateModifier(m) => write!(
+                f,
+                "{}",
+                tr("hotkey-error-duplicate-modifier", &[("modifier", m)])
+            ),
+            HotkeyError::MissingKey => write!(f, "{}", t("hotkey-error-missing-key")),
+            HotkeyError::MultipleKeys(a, b) => write!(
+                f,
+                "{}",
+                tr("hotkey-error-multiple-keys", &[("first", a), ("second", b)])
+            ),
+            HotkeyError::MissingModifier(k) => {
+                write!(f, "{}", tr("hotkey-error-missing-modifier", &[("key", k)]))
+            }
+        }
+    }
+}
+
+const KEY_NAMES: &[(&str, u32)] = &[
+    ("Backspace", 0x08),
+    ("Tab", 0x09),
+    ("Enter", 0x0D),
+    ("Pause", 0x13),
+    ("Esc", 0x1B),
+    ("Space", 0x20),
+    ("PageUp", 0x21),
+    ("PageDown", 0x22),
+    ("End", 0x23),
+    ("Home", 0x24),
+    ("Left", 0x25),
+    ("Up", 0x26),
+    ("Right", 0x27),
+    ("Down", 0x28),
+    ("PrintScreen", 0x2C),
+    ("Insert", 0x2D),
+    ("Delete", 0x2E),
+    ("Plus", 0xBB),
+    ("Comma", 0xBC),
+    ("Minus", 0xBD),
+    ("Period", 0xBE),
+];
+
+const KEY_ALIASES: &[(&str, &str)] = &[
+    ("Return", "Enter"),
+    ("Escape", "Esc"),
+    ("PgUp", "PageUp"),
+    ("PgDn", "PageDown"),
+    ("Ins", "Insert"),
+    ("Del", "Delete"),
+    ("PrtSc", "PrintScreen"),
+];
+
+fn parse_modifier(name: &str) -> Option<u32> {
+    match name.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => Some(MOD_CONTROL),
+        "alt" => Some(MOD_ALT),
+        "shift" => Some(MOD_SHIFT),
+        "win" | "super" | "meta" => Some(MOD_WIN),
+        _ => None,
+    }
+}
+
+fn parse_key(name: &str) -> Option<u32> {
+    let upper = name.to_ascii_uppercase();
+    if upper.len() == 1 {
+        let c = upper.as_bytes()[0];
+        if c.is_ascii_alphanumeric() {
+            return Some(c as u32);
+        }
+    }
+    if let Some(n) = upper.strip_prefix('F').and_then(|n| n.parse::<u32>().ok()) {
+        if (1..=24).contains(&n) {
+            return Some(0x70 + n - 1);
+        }
+    }
+    if let Some(n) = upper
+        .strip_prefix("NUMPAD")
+        .and_then(|n| n.parse::<u32>().ok())
+    {
+        if n <= 9 {
+            return Some(0x60 + n);
+        }
+    }
+    let name = KEY_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+        .map_or(name, |(_, canonical)| canonical);
+    KEY_NAMES
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, vk)| *vk)
+}
+
+fn key_name(vk: u32) -> String {
+    match vk {
+        0x30..=0x39 | 0x41..=0x5A => (vk as u8 as char).to_string(),
+        0x60..=0x69 => format!("NumPad{}", vk - 0x60),
+        0x70..=0x87 => format!("F{}", vk - 0x70 + 1),
+        _ => KEY_NAMES
+            .iter()
+            .find(|(_, v)| *v == vk)
+            .map_or_else(|| format!("0x{:02X}", vk), |(n, _)| n.to_string()),
+    }
+}
+
+/// Parses a chord like `Ctrl+Alt+T`. Names are case-insensitive.
+pub fn parse_chord(text: &str) -> Result<Chord, HotkeyError> {
+    if text.trim().is_empty() {
+        return Err(HotkeyError::Empty);
+    }
+    let mut modifiers = 0;
+    let mut key: Option<(u32, &str)> = None;
+    for part in text.split('+').map(str::trim) {
+        if part.is_empty() {
+            return Err(HotkeyError::UnknownKey(text.trim().to_string()));
+        }
+        if let Some(modifier) = parse_modifier(part) {
+            if modifiers & modifier != 0 {
+                return Err(HotkeyError::DuplicateModifier(part.to_string()));
+            }
+            modifiers |= modifier;
+        } else if let Some(vk) = parse_key(part) {
+            if let Some((_, previous)) = key {
+                return Err(HotkeyError::MultipleKeys(
+                    previous.to_string(),
+                    part.to_string(),
+                ));
+            }
+            key = Some((vk, part));
+        } else {
+            return Err(HotkeyError::UnknownKey(part.to_string()));
+        }
+    }
+    match key {
+        Some((vk, _)) => Ok(Chord { modifiers, key: vk }),
+        None => Err(HotkeyError::MissingKey),
+    }
+}
+
+/// Parses a chord for global registration, which requires a modifier
+pub fn parse_hotkey(text: &str) -> Result<Chord, HotkeyError> {
+    let chord = parse_chord(text)?;
+    if chord.modifiers == 0 {
+        return Err(HotkeyError::MissingModifier(chord.to_string()));
+    }
+    Ok(chord)
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (flag, name) in [
+            (MOD_CONTROL, "Ctrl"),
+            (MOD_ALT, "Alt"),
+            (MOD_SHIFT, "Shift"),
+            (MOD_WIN, "Win"),
+        ] {
+            if self.modifiers & flag != 0 {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{}", key_name(self.key))
+    }
+}
+
+/// Parses a binding's keys: one chord, or a comma-separated sequence like `Ctrl+Alt+T, 2`.
+/// Only the first chord is registered globally, so only it needs a modifier.
+pub fn parse_sequence(text: &str) -> Result<Vec<Chord>, HotkeyError> {
+    let mut parts = text.split(',');
+    let mut chords = vec![parse_hotkey(parts.next().unwrap_or_default())?];
+    for part in parts {
+        chords.push(parse_chord(part)?);
+    }
+    Ok(chords)
+}
+
+pub const SEQUENCE_TIMER_ID: usize = 1;
+
+// Ids for keys grabbed only while a sequence is pending
+const FOLLOW_UP_ID_BASE: i32 = 0x1000;
+
+struct HotkeyState {
+    matcher: SequenceMatcher<Action>,
+    registered: Vec<Chord>, // id = index + 1
+    follow_ups: Vec<Chord>, // id = FOLLOW_UP_ID_BASE + index
+    timeout_ms: u32,
+}
+
+static STATE: Mutex<Option<HotkeyState>> = Mutex::new(None);
+
+pub fn register(hwnd: HWND, id: i32, chord: Chord) -> bool {
+    let modifiers = HOT_KEY_MODIFIERS(chord.modifiers | MOD_NOREPEAT.0);
+    unsafe { RegisterHotKey(Some(hwnd), id, modifiers, chord.key) }.is_ok()
+}
+
+/// Registers the first chord of every configured hotkey
+pub fn register_all(hwnd: HWND, config: &Config) {
+    let mut failed = Vec::new();
+    let mut bindings: Vec<(Vec<Chord>, Action)> = Vec::new();
+    for binding in &config.hotkeys {
+        let sequence = match parse_sequence(&binding.keys) {
+            Ok(sequence) => sequence,
+            Err(e) => {
+                eprintln!("Invalid hotkey \"{}\": {}", binding.keys, e);
+                failed.push(tr(
+                    "hotkey-invalid",
+                    &[("keys", &binding.keys), ("error", &e)],
+                ));
+                continue;
+            }
+        };
+        if bindings.iter().any(|(s, _)| *s == sequence) {
+            eprintln!("Hotkey {} is bound more than once", binding.keys);
+            failed.push(tr("hotkey-duplicate", &[("keys", &binding.keys)]));
+            continue;
+        }
+        bindings.push((sequence, binding.action.clone()));
+    }
+
+    let matcher = SequenceMatcher::new(bindings);
+    let mut registered = Vec::new();
+    for chord in matcher.first_chords() {
+        if register(hwnd, registered.len() as i32 + 1, chord) {
+            eprintln!("Hotkey {} registered successfully", chord);
+            registered.push(chord);
+        } else {
+            eprintln!("Failed to register hotkey {}", chord);
+            failed.push(tr("hotkey-in-use", &[("keys", &chord)]));
+        }
+    }
+
+    *FAILED_HOTKEYS.lock().unwrap() = failed;
+    *STATE.lock().unwrap() = Some(HotkeyState {
+        matcher,
+        registered,
+        follow_ups: Vec::new(),
+        timeout_ms: config.sequence_timeout_ms,
+    });
+}
+
+pub fn unregister_all(hwnd: HWND) {
+    if let Some(state) = STATE.lock().unwrap().as_mut() {
+        end_sequence(hwnd, state);
+        for i in 0..state.registered.len() {
+            let _ = unsafe { UnregisterHotKey(Some(hwnd), i as i32 + 1) };
+        }
+        state.registered.clear();
+    }
+}
+
+fn end_sequence(hwnd: HWND, state: &mut HotkeyState) {
+    state.matcher.reset();
+    for i in 0..state.follow_ups.len() {
+        let _ = unsafe { UnregisterHotKey(Some(hwnd), FOLLOW_UP_ID_BASE + i as i32) };
+    }
+    state.follow_ups.clear();
+    let _ = unsafe { KillTimer(Some(hwnd), SEQUENCE_TIMER_ID) };
+}
+
+/// Handles `WM_HOTKEY`, returning the action to run once a binding is complete
+pub fn handle_hotkey(hwnd: HWND, lparam: LPARAM) -> Option<Action> {
+    // LPARAM holds the modifiers in the low word and the virtual-key code in the high word
+    let chord = Chord {
+        modifiers: (lparam.0 as u32 & 0xFFFF) & !MOD_NOREPEAT.0,
+        key: (lparam.0 as u32 >> 16) & 0xFFFF,
+    };
+    let mut guard = STATE.lock().unwrap();
+    let state = guard.as_mut()?;
+    match state.matcher.feed(chord) {
+        Step::Matched(action) => {
+            end_sequence(hwnd, state);
+            Some(action)
+        }
+        Step::Pending => {
+            // Grab the keys that can come next until the sequence times out
+            for i in 0..state.follow_ups.len() {
+                let _ = unsafe { UnregisterHotKey(Some(hwnd), FOLLOW_UP_ID_BASE + i as i32) };
+            }
+            state.follow_ups.clear();
+            for chord in state.matcher.next_chords() {
+                if state.registered.contains(&chord) {
+                    continue;
+                }
+                if register(
+                    hwnd,
+                    FOLLOW_UP_ID_BASE + state.follow_ups.len() as i32,
+                    chord,
+                ) {
+                    state.follow_ups.push(chord);
+                }
+            }
+            unsafe { SetTimer(Some(hwnd), SEQUENCE_TIMER_ID, state.timeout_ms, None) };
+            None
+        }
+        Step::NoMatch => {
+            end_sequence(hwnd, state);
+            None
+        }
+    }
+}
+
+/// Handles the sequence timer, running a pending binding that is complete on its own
+pub fn handle_timeout(hwnd: HWND) -> Option<Action> {
+    let mut guard = STATE.lock().unwrap();
+    let state = guard.as_mut()?;
+    let action = state.matcher.timeout();
+    end_sequence(hwnd, state);
+    action
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_modifiers_and_key() {
+        assert_eq!(
+            parse_hotkey("Ctrl+Shift+F12"),
+            Ok(Chord {
+                modifiers: MOD_CONTROL | MOD_SHIFT,
+                key: 0x7B,
+            })
+        );
+        assert_eq!(
+            parse_hotkey(" alt + t "),
+            Ok(Chord {
+                modifiers: MOD_ALT,
+                key: 'T' as u32,
+            })
+        );
+    }
+
+    #[test]
+    fn modifier_aliases() {
+        assert_eq!(parse_hotkey("Control+A"), parse_hotkey("Ctrl+A"));
+        assert_eq!(parse_hotkey("Super+A"), parse_hotkey("Win+A"));
+        assert_eq!(parse_hotkey("Meta+A"), parse_hotkey("Win+A"));
+        assert_eq!(parse_hotkey("Win+A").unwrap().modifiers, MOD_WIN);
+    }
+
+    #[test]
+    fn function_and_numpad_keys() {
+        assert_eq!(parse_chord("F1").unwrap().key, 0x70);
+        assert_eq!(parse_chord("F24").unwrap().key, 0x87);
+        assert_eq!(
+            parse_chord("F25"),
+            Err(HotkeyError::UnknownKey("F25".to_string()))
+        );
+        assert_eq!(parse_chord("NumPad0").unwrap().key, 0x60);
+        assert_eq!(parse_chord("numpad9").unwrap().key, 0x69);
+        assert_eq!(
+            parse_chord("NumPad10"),
+            Err(HotkeyError::UnknownKey("NumPad10".to_string()))
+        );
+    }
+
+    #[test]
+    fn key_aliases() {
+        assert_eq!(parse_chord("Escape"), parse_chord("Esc"));
+        assert_eq!(parse_chord("PgDn"), parse_chord("PageDown"));
+        assert_eq!(parse_chord("Del").unwrap().key, 0x2E);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_chord(""), Err(HotkeyError::Empty));
+        assert_eq!(parse_chord("   "), Err(HotkeyError::Empty));
+        assert_eq!(
+            parse_chord("Ctrl+Foo"),
+            Err(HotkeyError::UnknownKey("Foo".to_string()))
+        );
+        assert_eq!(
+            parse_chord("Ctrl++T"),
+            Err(HotkeyError::UnknownKey("Ctrl++T".to_string()))
+        );
+        assert_eq!(
+            parse_chord("Ctrl+Control+T"),
+            Err(HotkeyError::DuplicateModifier("Control".to_string()))
+        );
+        assert_eq!(parse_chord("Ctrl+Alt"), Err(HotkeyError::MissingKey));
+        assert_eq!(
+            parse_chord("Ctrl+A+B"),
+            Err(HotkeyError::MultipleKeys("A".to_string(), "B".to_string()))
+        );
+        assert_eq!(
+            parse_hotkey("F12"),
+            Err(HotkeyError::MissingModifier("F12".to_string()))
+        );
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for text in [
+            "Ctrl+Alt+T",
+            "Ctrl+Shift+F12",
+            "Win+NumPad5",
+            "Alt+PageDown",
+            "Ctrl+Alt+Shift+Win+Delete",
+            "F24",
+            "7",
+        ] {
+            let chord = parse_chord(text).unwrap();
+            assert_eq!(chord.to_string(), text);
+            assert_eq!(parse_chord(&chord.to_string()), Ok(chord));
+        }
+        // Aliases and case normalize to the canonical names
+        assert_eq!(
+            parse_chord("control+super+escape").unwrap().to_string(),
+            "Ctrl+Win+Esc"
+        );
+    }
+
+    #[test]
+    fn sequences() {
+        let sequence = parse_sequence("Ctrl+Alt+T, 2").unwrap();
+        assert_eq!(sequence.len(), 2);
+        assert_eq!(sequence[1].modifiers, 0);
+        assert_eq!(
+            parse_sequence("T, 2"),
+            Err(HotkeyError::MissingModifier("T".to_string()))
+        );
+        assert_eq!(parse_sequence("Ctrl+Alt+T, "), Err(HotkeyError::Empty));
+    }
+}
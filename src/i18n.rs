@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::Mutex;
+
+use windows::Win32::Globalization::GetUserDefaultLocaleName;
+
+/// Shipped message catalogs, by language. English is the fallback for missing keys.
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+];
+
+struct Catalog {
+    messages: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+static CATALOG: Mutex<Option<Catalog>> = Mutex::new(None);
+
+/// Parses a catalog in a subset of the Fluent syntax: `key = value` messages,
+/// indented continuation lines, `#` comments and `{ $name }` placeholders
+pub fn parse(source: &str) -> HashMap<String, String> {
+    let mut messages = HashMap::new();
+    let mut current: Option<(String, Vec<String>)> = None;
+    let finish = |current: Option<(String, Vec<String>)>, messages: &mut HashMap<_, _>| {
+        if let Some((key, mut lines)) = current {
+            while lines.last().is_some_and(|l| l.trim().is_empty()) {
+                lines.pop();
+            }
+            // Continuation lines lose their common indentation
+            let indent = lines
+                .iter()
+                .skip(1)
+                .filter(|l| !l.trim().is_empty())
+                .map(|l| l.len() - l.trim_start().len())
+                .min()
+                .unwrap_or(0);
+            let mut value: Vec<&str> = Vec::new();
+            for (i, line) in lines.iter().enumerate() {
+                if i == 0 {
+                    if !line.is_empty() {
+                        value.push(line);
+                    }
+                } else {
+                    value.push(line.get(indent..).unwrap_or_default());
+                }
+            }
+            messages.insert(key, value.join("\n"));
+        }
+    };
+    for line in source.lines() {
+        if let Some((_, lines)) = current.as_mut() {
+            if line.starts_with(' ') || line.trim().is_empty() {
+                lines.push(line.trim_end().to_string());
+                continue;
+            }
+        }
+        finish(current.take(), &mut messages);
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            current = Some((key.trim().to_string(), vec![value.trim().to_string()]));
+        }
+    }
+    finish(current, &mut messages);
+    messages
+}
+
+/// Language of the Windows user locale, e.g. `de` for `de-DE`
+fn system_language() -> String {
+    let mut name = [0u16; 85];
+    let len = unsafe { GetUserDefaultLocaleName(&mut name) };
+    if len <= 1 {
+        return "en".to_string();
+    }
+    let name = String::from_utf16_lossy(&name[..len as usize - 1]);
+    name.split('-').next().unwrap_or("en").to_ascii_lowercase()
+}
+
+fn load(language: &str) -> Catalog {
+    let find = |language: &str| {
+        LOCALES
+            .iter()
+            .find(|(l, _)| l.eq_ignore_ascii_case(language))
+            .map(|(_, source)| parse(source))
+    };
+    let fallback = find("en").unwrap_or_default();
+    let messages = find(language).unwrap_or_else(|| {
+        if language != "en" {
+            eprintln!("No translation for language '{}', using English", language);
+        }
+        fallback.clone()
+    });
+    Catalog { messages, fallback }
+}
+
+/// Picks the language from the config, or the Windows locale when not set
+pub fn set_language(language: Option<&str>) {
+    let language = match language {
+        Some(language) => language.split('-').next().unwrap_or(language).to_string(),
+        None => system_language(),
+    };
+    *CATALOG.lock().unwrap() = Some(load(&language));
+}
+
+/// Switches to `language` until the guard is dropped, so tests that check messages don't
+/// see another test's language
+#[cfg(test)]
+pub fn lock_language(language: &str) -> std::sync::MutexGuard<'static, ()> {
+    static LANGUAGE: Mutex<()> = Mutex::new(());
+    // A failed test must not fail the others
+    let guard = LANGUAGE.lock().unwrap_or_else(|e| e.into_inner());
+    set_language(Some(language));
+    guard
+}
+
+/// Looks up a message and fills in its `{ $name }` placeholders
+pub fn tr(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let message = {
+        let mut catalog = CATALOG.lock().unwrap();
+        let catalog = catalog.get_or_insert_with(|| load(&system_language()));
+        catalog
+            .messages
+            .get(key)
+            .or_else(|| catalog.fallback.get(key))
+            .cloned()
+    };
+    let Some(mut text) = message else {
+        return key.to_string();
+    };
+    // Arguments may be localized themselves, so they are formatted without the lock
+    for (name, value) in args {
+        let value = value.to_string();
+        text = text
+            .replace(&format!("{{ ${} }}", name), &value)
+            .replace(&format!("{{${}}}", name), &value);
+    }
+    text
+}
+
+/// Looks up a message without placeholders
+pub fn t(key: &str) -> String {
+    tr(key, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::*;
+
+    #[test]
+    fn every_locale_has_the_english_keys() {
+        let keys = |source| parse(source).into_keys().collect::<BTreeSet<_>>();
+        let english = keys(LOCALES[0].1);
+        for (language, source) in LOCALES {
+            assert_eq!(keys(source), english, "{}", language);
+        }
+
+        // Every catalog in locales/ is shipped
+        let mut files: Vec<String> = std::fs::read_dir("locales")
+            .unwrap()
+            .filter_map(|entry| {
+                let name = entry.unwrap().file_name().into_string().unwrap();
+                name.strip_suffix(".ftl").map(str::to_string)
+            })
+            .collect();
+        files.sort();
+        let mut shipped: Vec<String> = LOCALES.iter().map(|(l, _)| l.to_string()).collect();
+        shipped.sort();
+        assert_eq!(files, shipped);
+    }
+}
@@ -0,0 +1,132 @@
+use windows::Win32::Graphics::Gdi::{
+    CreateBitmap, CreateDIBSection, DeleteObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+    DIB_RGB_COLORS, HGDIOBJ,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateIconIndirect, GetSystemMetrics, HICON, ICONINFO, SM_CXSMICON,
+};
+
+use crate::config::Config;
+use crate::topology;
+
+// Colors as 0xAARRGGBB, premultiplied
+const ON_FILL: u32 = 0xFFF0F0F0;
+const OFF_FILL: u32 = 0xFF5A5A5A;
+const BORDER: u32 = 0xFF1E1E1E;
+
+/// A monitor in desktop coordinates
+struct Screen {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    off: bool,
+}
+
+/// Draws the monitor layout scaled into a `size`×`size` icon, top row first
+fn render(screens: &[Screen], size: i32) -> Vec<u32> {
+    let mut pixels = vec![0u32; (size * size) as usize];
+    let left = screens.iter().map(|s| s.x).min().unwrap_or(0);
+    let top = screens.iter().map(|s| s.y).min().unwrap_or(0);
+    let right = screens
+        .iter()
+        .map(|s| s.x + s.width as i32)
+        .max()
+        .unwrap_or(1);
+    let bottom = screens
+        .iter()
+        .map(|s| s.y + s.height as i32)
+        .max()
+        .unwrap_or(1);
+    let (width, height) = ((right - left).max(1), (bottom - top).max(1));
+
+    // Keep the aspect ratio and center the layout
+    let scale = (size - 2) as f64 / width.max(height) as f64;
+    let offset_x = (size as f64 - width as f64 * scale) / 2.0;
+    let offset_y = (size as f64 - height as f64 * scale) / 2.0;
+    let to_pixel = |value: i32, origin: i32, offset: f64| {
+        ((value - origin) as f64 * scale + offset).round() as i32
+    };
+
+    for screen in screens {
+        let x0 = to_pixel(screen.x, left, offset_x).clamp(0, size - 1);
+        let y0 = to_pixel(screen.y, top, offset_y).clamp(0, size - 1);
+        let x1 = to_pixel(screen.x + screen.width as i32, left, offset_x).clamp(x0 + 1, size);
+        let y1 = to_pixel(screen.y + screen.height as i32, top, offset_y).clamp(y0 + 1, size);
+        let fill = if screen.off { OFF_FILL } else { ON_FILL };
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let edge = x == x0 || y == y0 || x == x1 - 1 || y == y1 - 1;
+                pixels[(y * size + x) as usize] = if edge { BORDER } else { fill };
+            }
+        }
+    }
+    pixels
+}
+
+fn create_icon(pixels: &[u32], size: i32) -> Option<HICON> {
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: size,
+            biHeight: -size, // top-down
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut bits = std::ptr::null_mut();
+    let color =
+        unsafe { CreateDIBSection(None, &info, DIB_RGB_COLORS, &mut bits, None, 0) }.ok()?;
+    unsafe { std::ptr::copy_nonoverlapping(pixels.as_ptr(), bits as *mut u32, pixels.len()) };
+    // The alpha channel decides transparency, the mask is unused but required
+    let mask = unsafe { CreateBitmap(size, size, 1, 1, None) };
+    let icon_info = ICONINFO {
+        fIcon: true.into(),
+        hbmMask: mask,
+        hbmColor: color,
+        ..Default::default()
+    };
+    let icon = unsafe { CreateIconIndirect(&icon_info) };
+    unsafe {
+        let _ = DeleteObject(HGDIOBJ(color.0));
+        let _ = DeleteObject(HGDIOBJ(mask.0));
+    }
+    icon.ok()
+}
+
+/// Renders the tray icon as a miniature of the monitor layout with off monitors greyed out.
+/// None when there is no layout to show.
+pub fn layout_icon(config: &Config) -> Option<HICON> {
+    let mut screens: Vec<Screen> = topology::details()
+        .iter()
+        .filter(|(m, _)| !config.saved_modes.contains_key(m))
+        .map(|(_, details)| Screen {
+            x: details.x,
+            y: details.y,
+            width: details.width,
+            height: details.height,
+            off: false,
+        })
+        .collect();
+    // Off monitors can't be enumerated, their place is remembered from before
+    screens.extend(
+        config
+            .saved_modes
+            .values()
+            .map(|&(width, height, x, y)| Screen {
+                x,
+                y,
+                width,
+                height,
+                off: true,
+            }),
+    );
+    if screens.is_empty() {
+        return None;
+    }
+    let size = unsafe { GetSystemMetrics(SM_CXSMICON) }.max(16);
+    create_icon(&render(&screens, size), size)
+}
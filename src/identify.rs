@@ -0,0 +1,200 @@
+use std::sync::{Mutex, Once};
+
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::{
+    BeginPaint, CreateFontW, CreateSolidBrush, DeleteObject, DrawTextW, EndPaint, FillRect,
+    SelectObject, SetBkMode, SetTextColor, CLEARTYPE_QUALITY, CLIP_DEFAULT_PRECIS, DEFAULT_CHARSET,
+    DT_CENTER, DT_END_ELLIPSIS, DT_SINGLELINE, DT_VCENTER, FW_BOLD, HDC, HGDIOBJ,
+    OUT_DEFAULT_PRECIS, PAINTSTRUCT, TRANSPARENT,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GetClientRect, GetMessageW,
+    KillTimer, RegisterClassW, SetTimer, TranslateMessage, MSG, WM_PAINT, WM_TIMER, WNDCLASSW,
+    WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP, WS_VISIBLE,
+};
+
+use crate::config::Config;
+use crate::monitors::{display_number, monitor_name};
+use crate::topology;
+
+const WINDOW_CLASS: PCWSTR = w!("ScreenOffIdentifyClass");
+
+const HIDE_TIMER_ID: usize = 1;
+const SHOW_MS: u32 = 3000;
+
+const BACKGROUND: COLORREF = COLORREF(0x00202020);
+const TEXT: COLORREF = COLORREF(0x00FFFFFF);
+
+/// An overlay window and what it shows
+struct Overlay {
+    hwnd: HWND,
+    number: String,
+    name: String,
+}
+
+// The window handles are only used from the UI thread
+unsafe impl Send for Overlay {}
+
+static OVERLAYS: Mutex<Vec<Overlay>> = Mutex::new(Vec::new());
+
+static REGISTER_CLASS: Once = Once::new();
+
+/// Shows the display number and name, as in the menu, in the middle of every active monitor
+pub fn show(config: &Config) {
+    hide();
+    let Ok(hinstance) = (unsafe { GetModuleHandleW(PCWSTR::null()) }) else {
+        return;
+    };
+    REGISTER_CLASS.call_once(|| {
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: hinstance.into(),
+            lpszClassName: WINDOW_CLASS,
+            ..Default::default()
+        };
+        unsafe { RegisterClassW(&class) };
+    });
+
+    let names = topology::monitors();
+    let mut overlays = OVERLAYS.lock().unwrap();
+    for (monitor, details) in topology::details().iter() {
+        let friendly_name = names
+            .iter()
+            .find(|(m, _)| m == monitor)
+            .map(|(_, name)| name.as_str())
+            .unwrap_or_default();
+        let (width, height) = (details.width as i32 / 3, details.height as i32 / 3);
+        let hwnd = unsafe {
+            CreateWindowExW(
+                WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+                WINDOW_CLASS,
+                PCWSTR::null(),
+                WS_POPUP | WS_VISIBLE,
+                details.x + (details.width as i32 - width) / 2,
+                details.y + (details.height as i32 - height) / 2,
+                width,
+                height,
+                None,
+                None,
+                Some(hinstance.into()),
+                None,
+            )
+        };
+        let Ok(hwnd) = hwnd else {
+            continue;
+        };
+        overlays.push(Overlay {
+            hwnd,
+            number: display_number(monitor)
+                .map(|n| n.to_string())
+                .unwrap_or_default(),
+            name: monitor_name(config, monitor, friendly_name),
+        });
+    }
+    if let Some(first) = overlays.first() {
+        unsafe { SetTimer(Some(first.hwnd), HIDE_TIMER_ID, SHOW_MS, None) };
+    }
+}
+
+pub fn hide() {
+    let overlays = std::mem::take(&mut *OVERLAYS.lock().unwrap());
+    for overlay in overlays {
+        let _ = unsafe { KillTimer(Some(overlay.hwnd), HIDE_TIMER_ID) };
+        let _ = unsafe { DestroyWindow(overlay.hwnd) };
+    }
+}
+
+/// Runs messages until the overlays are gone, for a command line without a tray instance
+pub fn wait() {
+    let mut msg = MSG::default();
+    while !OVERLAYS.lock().unwrap().is_empty()
+        && unsafe { GetMessageW(&mut msg, None, 0, 0) }.as_bool()
+    {
+        let _ = unsafe { TranslateMessage(&msg) };
+        unsafe { DispatchMessageW(&msg) };
+    }
+}
+
+fn draw_text(hdc: HDC, text: &str, height: i32, rect: RECT) {
+    let font = unsafe {
+        CreateFontW(
+            height,
+            0,
+            0,
+            0,
+            FW_BOLD.0 as i32,
+            0,
+            0,
+            0,
+            DEFAULT_CHARSET,
+            OUT_DEFAULT_PRECIS,
+            CLIP_DEFAULT_PRECIS,
+            CLEARTYPE_QUALITY,
+            0,
+            w!("Segoe UI"),
+        )
+    };
+    let old_font = unsafe { SelectObject(hdc, HGDIOBJ(font.0)) };
+    let mut text: Vec<u16> = text.encode_utf16().collect();
+    let mut rect = rect;
+    unsafe {
+        DrawTextW(
+            hdc,
+            &mut text,
+            &mut rect,
+            DT_CENTER | DT_VCENTER | DT_SINGLELINE | DT_END_ELLIPSIS,
+        )
+    };
+    unsafe { SelectObject(hdc, old_font) };
+    let _ = unsafe { DeleteObject(HGDIOBJ(font.0)) };
+}
+
+fn paint(hwnd: HWND) {
+    let (number, name) = {
+        let overlays = OVERLAYS.lock().unwrap();
+        match overlays.iter().find(|o| o.hwnd == hwnd) {
+            Some(overlay) => (overlay.number.clone(), overlay.name.clone()),
+            None => (String::new(), String::new()),
+        }
+    };
+    let mut ps = PAINTSTRUCT::default();
+    let hdc = unsafe { BeginPaint(hwnd, &mut ps) };
+    let mut rect = RECT::default();
+    let _ = unsafe { GetClientRect(hwnd, &mut rect) };
+    let brush = unsafe { CreateSolidBrush(BACKGROUND) };
+    unsafe { FillRect(hdc, &rect, brush) };
+    let _ = unsafe { DeleteObject(HGDIOBJ(brush.0)) };
+    unsafe { SetBkMode(hdc, TRANSPARENT) };
+    unsafe { SetTextColor(hdc, TEXT) };
+
+    // The number takes the top two thirds, the name the rest
+    let split = rect.top + (rect.bottom - rect.top) * 2 / 3;
+    let height = rect.bottom - rect.top;
+    draw_text(
+        hdc,
+        &number,
+        height / 2,
+        RECT {
+            bottom: split,
+            ..rect
+        },
+    );
+    draw_text(hdc, &name, height / 8, RECT { top: split, ..rect });
+    let _ = unsafe { EndPaint(hwnd, &ps) };
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_PAINT => paint(hwnd),
+        WM_TIMER if wparam.0 == HIDE_TIMER_ID => hide(),
+        _ => return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+    LRESULT(0)
+}
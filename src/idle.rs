@@ -0,0 +1,81 @@
+use std::sync::Mutex;
+
+use windows::Win32::System::SystemInformation::GetTickCount;
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+use windows::Win32::UI::WindowsAndMessaging::SetTimer;
+
+use crate::config::Config;
+use crate::monitors::{is_busy, track, turn_off, turn_on};
+use crate::tray::TRAY_HWND;
+
+pub const TIMER_ID: usize = 3;
+
+// Checked every second so monitors come back right after the first input
+const TICK_MS: u32 = 1000;
+
+/// Monitors turned off because the user was idle, restored on the next input
+static IDLE_OFF: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Milliseconds since the last keyboard or mouse input
+fn idle_ms() -> u32 {
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    if !unsafe { GetLastInputInfo(&mut info) }.as_bool() {
+        return 0;
+    }
+    // Both are tick counts, which wrap after 49.7 days
+    unsafe { GetTickCount() }.wrapping_sub(info.dwTime)
+}
+
+pub fn start(config: &Config) {
+    let hwnd = unsafe { TRAY_HWND };
+    if config.idle.enabled && !hwnd.is_invalid() {
+        eprintln!(
+            "Turning monitors off after {} idle minutes",
+            config.idle.minutes
+        );
+        unsafe { SetTimer(Some(hwnd), TIMER_ID, TICK_MS, None) };
+    }
+}
+
+pub fn tick(config: &mut Config) {
+    // Checked again once the queued changes are done
+    if is_busy() {
+        return;
+    }
+    let idle = idle_ms();
+    // Not held while toggling, which may run this again
+    let idle_off: Vec<String> = {
+        let mut idle_off = IDLE_OFF.lock().unwrap();
+        // Turned back on some other way in the meantime
+        idle_off.retain(|m| config.saved_modes.contains_key(m));
+        idle_off.clone()
+    };
+
+    if idle_off.is_empty() {
+        if config.saved_modes.is_empty() && idle >= config.idle.minutes.max(1) * 60_000 {
+            eprintln!("Idle for {} minutes", config.idle.minutes);
+            let monitors = config.secondary_monitors.clone();
+            track(
+                config,
+                |config| turn_off(config, &monitors),
+                |config, already_off| {
+                    *IDLE_OFF.lock().unwrap() = config
+                        .saved_modes
+                        .keys()
+                        .filter(|m| !already_off.contains(m))
+                        .cloned()
+                        .collect();
+                },
+            );
+        }
+    } else if idle < TICK_MS * 2 {
+        IDLE_OFF.lock().unwrap().clear();
+        if config.idle.restore_on_input {
+            eprintln!("Input after idle, turning monitors back on");
+            turn_on(config, &idle_off);
+        }
+    }
+}
@@ -1,7 +1,32 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod a11y;
+mod actions;
+mod app_rules;
+mod cli;
 mod config;
+mod confirm;
+mod hooks;
+mod hotkeys;
+mod i18n;
+mod icon;
+mod identify;
+mod idle;
+mod menu;
 mod monitors;
+mod notify;
+mod overlay;
+mod power;
+mod recovery;
+mod rename;
+mod schedule;
+mod scheduler;
+mod sequences;
+mod service;
+mod session;
+mod theme;
+mod timer;
+mod topology;
 mod tray;
 
 use windows::core::PCWSTR;
@@ -15,18 +40,19 @@ use windows::Win32::System::Threading::{
 use windows::Win32::UI::HiDpi::{
     SetProcessDpiAwarenessContext, DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
 };
-use windows::Win32::UI::Input::KeyboardAndMouse::{
-    RegisterHotKey, UnregisterHotKey, HOT_KEY_MODIFIERS, MOD_ALT, MOD_CONTROL,
-};
-use windows::Win32::UI::Shell::{
-    Shell_NotifyIconW, NIF_ICON, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NOTIFYICONDATAW,
-};
 use windows::Win32::UI::WindowsAndMessaging::{
-    CreateWindowExW, DispatchMessageW, GetMessageW, RegisterClassW, ShowWindow, TranslateMessage,
-    CW_USEDEFAULT, MSG, SW_HIDE, WINDOW_EX_STYLE, WM_USER, WNDCLASSW, WS_OVERLAPPEDWINDOW,
+    CreateWindowExW, RegisterClassW, ShowWindow, CW_USEDEFAULT, SW_HIDE, WINDOW_EX_STYLE,
+    WNDCLASSW, WS_OVERLAPPEDWINDOW,
 };
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let headless = match cli::run(&args) {
+        cli::Startup::Exit(code) => std::process::exit(code),
+        cli::Startup::Tray => false,
+        cli::Startup::Headless => true,
+    };
+
     // Prevent multiple instances
     unsafe {
         let mutex_name: Vec<u16> = "Global\\ScreenOffMutex"
@@ -64,6 +90,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     let mut config = config::load_config();
+    i18n::set_language(config.language.as_deref());
+    let headless = headless || config.headless;
+    // Nobody may be around to answer a prompt without the tray
+    recovery::check_unclean_shutdown(&mut config, !headless);
 
     // Only update secondary monitors if starting fresh (no saved disabled state)
     // If monitors were disabled when app closed, keep that state
@@ -79,19 +109,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
     eprintln!("=========================");
 
-    let icon_id = if config.saved_modes.is_empty() {
-        tray::IDI_SCREEN_ON
-    } else {
-        tray::IDI_SCREEN_OFF
-    };
-
     let config_box = Box::new(config);
     unsafe { tray::CONFIG = Box::into_raw(config_box) };
 
     let hinstance = unsafe { GetModuleHandleW(PCWSTR::null()) }.unwrap();
     let hinstance = HINSTANCE(hinstance.0);
     unsafe { tray::HINSTANCE = hinstance };
-    let class_name_wide: Vec<u16> = "ScreenOffTrayClass"
+    let class_name_wide: Vec<u16> = tray::WINDOW_CLASS
         .encode_utf16()
         .chain(std::iter::once(0))
         .collect();
@@ -129,45 +153,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let _ = unsafe { ShowWindow(hwnd, SW_HIDE) };
 
-    // Register hotkey CTRL+ALT+T
-    unsafe {
-        if RegisterHotKey(
-            Some(hwnd),
-            1,
-            HOT_KEY_MODIFIERS(MOD_CONTROL.0 | MOD_ALT.0),
-            'T' as u32,
-        )
-        .is_err()
-        {
-            eprintln!("Failed to register hotkey CTRL+ALT+T");
-        } else {
-            eprintln!("Hotkey CTRL+ALT+T registered successfully");
-        }
-    };
-
-    let mut nid: NOTIFYICONDATAW = unsafe { std::mem::zeroed() };
-    nid.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
-    nid.hWnd = hwnd;
-    nid.uID = 1;
-    nid.uFlags = NIF_ICON | NIF_MESSAGE | NIF_TIP;
-    nid.uCallbackMessage = WM_USER;
-    nid.hIcon = tray::load_icon_from_resource(icon_id);
-    let tip = "Screen Off";
-    let tip_wide: Vec<u16> = tip.encode_utf16().chain(std::iter::once(0)).collect();
-    nid.szTip[..tip_wide.len()].copy_from_slice(&tip_wide);
-
-    unsafe { Shell_NotifyIconW(NIM_ADD, &nid).unwrap() };
-
-    let mut msg = MSG::default();
-    while unsafe { GetMessageW(&mut msg, None, 0, 0) }.as_bool() {
-        let _ = unsafe { TranslateMessage(&msg) };
-        unsafe {
-            DispatchMessageW(&msg);
-        }
+    if headless {
+        eprintln!("Running without a tray icon");
+    } else {
+        unsafe { tray::add_icon(hwnd, &*tray::CONFIG) };
     }
+    unsafe { service::start(hwnd, &mut *tray::CONFIG) };
+    service::run();
+    unsafe { service::stop(hwnd, &mut *tray::CONFIG) };
+    tray::remove_icon(hwnd);
 
-    let _ = unsafe { Shell_NotifyIconW(NIM_DELETE, &nid) };
-    let _ = unsafe { UnregisterHotKey(Some(hwnd), 1) };
     unsafe {
         let _ = Box::from_raw(tray::CONFIG);
     };
@@ -0,0 +1,560 @@
+use std::collections::HashMap;
+
+use crate::i18n::{t, tr};
+
+/// What choosing a menu item does
+#[derive(Clone, Debug, PartialEq)]
+pub enum MenuCommand {
+    SelectMonitor(String), // device name
+    TurnOffSelected,
+    TurnBackOn,
+    TimedOff(u32), // minutes
+    MonitorOn(String),
+    MonitorOff(String),
+    Rename(String), // device name
+    Identify,
+    ToggleStartup,
+    Exit,
+}
+
+impl MenuCommand {
+    /// Whether the menu opens again after this command, to make several choices in a row
+    pub fn keeps_menu_open(&self) -> bool {
+        matches!(
+            self,
+            MenuCommand::SelectMonitor(_) | MenuCommand::ToggleStartup | MenuCommand::Identify
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ItemKind {
+    Label,
+    Separator,
+    Command(MenuCommand),
+    Submenu(Vec<Item>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Item {
+    pub id: u32,
+    pub label: String,
+    pub checked: bool,
+    pub disabled: bool,
+    pub kind: ItemKind,
+}
+
+/// Everything the tray menu shows, gathered from the app state
+#[derive(Clone, Debug, Default)]
+pub struct MenuState {
+    pub monitors: Vec<(String, String)>, // device name, friendly name or alias
+    pub details: HashMap<String, String>, // device name -> connector, mode and position
+    pub primary: Option<String>,
+    pub selected: Vec<String>,      // secondary monitors
+    pub off: Vec<(String, String)>, // device name, label
+    pub config_error: Option<String>,
+    pub failed_hotkeys: Vec<String>,
+    pub next_event: Option<String>,
+    pub timer_status: Option<String>,
+    pub timer_presets: Vec<u32>,
+    pub startup_enabled: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Menu {
+    pub items: Vec<Item>,
+}
+
+struct Builder {
+    next_id: u32,
+}
+
+impl Builder {
+    fn item(&mut self, label: impl Into<String>, kind: ItemKind) -> Item {
+        // 0 is what TrackPopupMenu returns when nothing was chosen
+        self.next_id += 1;
+        Item {
+            id: self.next_id,
+            label: label.into(),
+            checked: false,
+            disabled: false,
+            kind,
+        }
+    }
+
+    fn label(&mut self, label: impl Into<String>) -> Item {
+        Item {
+            disabled: true,
+            ..self.item(label, ItemKind::Label)
+        }
+    }
+
+    fn separator(&mut self) -> Item {
+        self.item("", ItemKind::Separator)
+    }
+
+    fn command(&mut self, label: impl Into<String>, command: MenuCommand) -> Item {
+        self.item(label, ItemKind::Command(command))
+    }
+}
+
+/// Escapes `&` in text shown in a label, which would otherwise mark an access key
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&&")
+}
+
+/// The access key of a label: the character after a single `&`
+pub fn mnemonic(label: &str) -> Option<char> {
+    let mut chars = label.chars();
+    while let Some(c) = chars.next() {
+        if c == '&' {
+            match chars.next() {
+                Some('&') => continue,
+                other => return other,
+            }
+        }
+    }
+    None
+}
+
+impl Menu {
+    pub fn build(state: &MenuState) -> Menu {
+        let mut b = Builder { next_id: 0 };
+        let mut items = Vec::new();
+
+        // Config and hotkey problems and the next scheduled event, followed by a separator
+        if let Some(error) = &state.config_error {
+            items.push(b.label(escape(&tr("config-invalid", &[("error", error)]))));
+        }
+        for failure in &state.failed_hotkeys {
+            items.push(b.label(escape(failure)));
+        }
+        if let Some(next_event) = &state.next_event {
+            items.push(b.label(escape(next_event)));
+        }
+        if !items.is_empty() {
+            items.push(b.separator());
+        }
+
+        let turn_off_items = |b: &mut Builder| -> Vec<Item> {
+            state
+                .monitors
+                .iter()
+                .filter(|(m, _)| {
+                    // Covered monitors are still listed, but already off
+                    Some(m) != state.primary.as_ref() && !state.off.iter().any(|(o, _)| o == m)
+                })
+                .map(|(m, name)| {
+                    b.command(
+                        tr("menu-turn-off-monitor", &[("name", &escape(name))]),
+                        MenuCommand::MonitorOff(m.clone()),
+                    )
+                })
+                .collect()
+        };
+
+        if !state.off.is_empty() {
+            items.push(b.command(t("menu-turn-back-on"), MenuCommand::TurnBackOn));
+            if let Some(status) = &state.timer_status {
+                items.push(b.label(escape(status)));
+            }
+            // With a single monitor off, "Turn back on" already covers it
+            if state.off.len() > 1 {
+                for (m, label) in &state.off {
+                    items.push(b.command(
+                        tr("menu-turn-on-monitor", &[("name", &escape(label))]),
+                        MenuCommand::MonitorOn(m.clone()),
+                    ));
+                }
+            }
+            items.extend(turn_off_items(&mut b));
+            items.push(b.separator());
+            items.push(Item {
+                checked: state.startup_enabled,
+                ..b.command(t("menu-start-on-sign-in"), MenuCommand::ToggleStartup)
+            });
+            items.push(b.separator());
+            items.push(b.command(t("menu-exit"), MenuCommand::Exit));
+        } else {
+            items.push(b.label(t("menu-select-monitors")));
+            items.push(b.command(t("menu-identify"), MenuCommand::Identify));
+            for (m, name) in &state.monitors {
+                let label = match state.details.get(m) {
+                    Some(details) => tr(
+                        "menu-monitor-details",
+                        &[("name", &escape(name)), ("details", &escape(details))],
+                    ),
+                    None => escape(name),
+                };
+                items.push(Item {
+                    checked: state.selected.contains(m),
+                    ..b.command(label, MenuCommand::SelectMonitor(m.clone()))
+                });
+            }
+            items.push(b.separator());
+            items.push(b.command(t("menu-turn-off-selected"), MenuCommand::TurnOffSelected));
+            if !state.timer_presets.is_empty() {
+                let presets = state
+                    .timer_presets
+                    .iter()
+                    .map(|m| {
+                        b.command(
+                            tr("menu-minutes", &[("minutes", m)]),
+                            MenuCommand::TimedOff(*m),
+                        )
+                    })
+                    .collect();
+                items.push(b.item(t("menu-turn-off-for"), ItemKind::Submenu(presets)));
+            }
+            items.extend(turn_off_items(&mut b));
+            let rename = state
+                .monitors
+                .iter()
+                .map(|(m, name)| b.command(escape(name), MenuCommand::Rename(m.clone())))
+                .collect();
+            items.push(b.item(t("menu-rename"), ItemKind::Submenu(rename)));
+            items.push(b.separator());
+            items.push(Item {
+                checked: state.startup_enabled,
+                ..b.command(t("menu-start-on-sign-in"), MenuCommand::ToggleStartup)
+            });
+            items.push(b.command(t("menu-exit"), MenuCommand::Exit));
+        }
+        Menu { items }
+    }
+
+    /// Finds an item by id, including items in submenus
+    pub fn find(&self, id: u32) -> Option<&Item> {
+        fn find_in(items: &[Item], id: u32) -> Option<&Item> {
+            items.iter().find_map(|item| {
+                if item.id == id {
+                    Some(item)
+                } else if let ItemKind::Submenu(children) = &item.kind {
+                    find_in(children, id)
+                } else {
+                    None
+                }
+            })
+        }
+        find_in(&self.items, id)
+    }
+
+    /// The command of an enabled item, for dispatching a chosen id
+    pub fn command(&self, id: u32) -> Option<&MenuCommand> {
+        match self.find(id) {
+            Some(Item {
+                kind: ItemKind::Command(command),
+                disabled: false,
+                ..
+            }) => Some(command),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::lock_language;
+
+    fn monitors() -> Vec<(String, String)> {
+        [
+            ("\\\\.\\DISPLAY1", "Laptop"),
+            ("\\\\.\\DISPLAY2", "Dell & Co"),
+            ("\\\\.\\DISPLAY3", "LG"),
+        ]
+        .iter()
+        .map(|(m, name)| (m.to_string(), name.to_string()))
+        .collect()
+    }
+
+    fn on_state() -> MenuState {
+        MenuState {
+            monitors: monitors(),
+            primary: Some("\\\\.\\DISPLAY1".to_string()),
+            selected: vec!["\\\\.\\DISPLAY2".to_string()],
+            timer_presets: vec![25, 50],
+            startup_enabled: true,
+            ..Default::default()
+        }
+    }
+
+    fn off_state() -> MenuState {
+        MenuState {
+            off: vec![
+                ("\\\\.\\DISPLAY2".to_string(), "Dell & Co".to_string()),
+                ("\\\\.\\DISPLAY3".to_string(), "LG".to_string()),
+            ],
+            timer_status: Some("Back on in 12 minutes".to_string()),
+            ..on_state()
+        }
+    }
+
+    fn all_items(items: &[Item]) -> Vec<&Item> {
+        let mut all = Vec::new();
+        for item in items {
+            all.push(item);
+            if let ItemKind::Submenu(children) = &item.kind {
+                all.extend(all_items(children));
+            }
+        }
+        all
+    }
+
+    fn commands(menu: &Menu) -> Vec<MenuCommand> {
+        all_items(&menu.items)
+            .into_iter()
+            .filter_map(|item| menu.command(item.id).cloned())
+            .collect()
+    }
+
+    fn item_with(menu: &Menu, command: &MenuCommand) -> Item {
+        all_items(&menu.items)
+            .into_iter()
+            .find(|item| item.kind == ItemKind::Command(command.clone()))
+            .cloned()
+            .unwrap()
+    }
+
+    #[test]
+    fn on_menu() {
+        let _language = lock_language("en");
+        let menu = Menu::build(&on_state());
+        assert_eq!(menu.items[0].kind, ItemKind::Label);
+        assert_eq!(
+            commands(&menu),
+            vec![
+                MenuCommand::Identify,
+                MenuCommand::SelectMonitor("\\\\.\\DISPLAY1".to_string()),
+                MenuCommand::SelectMonitor("\\\\.\\DISPLAY2".to_string()),
+                MenuCommand::SelectMonitor("\\\\.\\DISPLAY3".to_string()),
+                MenuCommand::TurnOffSelected,
+                MenuCommand::TimedOff(25),
+                MenuCommand::TimedOff(50),
+                MenuCommand::MonitorOff("\\\\.\\DISPLAY2".to_string()),
+                MenuCommand::MonitorOff("\\\\.\\DISPLAY3".to_string()),
+                MenuCommand::Rename("\\\\.\\DISPLAY1".to_string()),
+                MenuCommand::Rename("\\\\.\\DISPLAY2".to_string()),
+                MenuCommand::Rename("\\\\.\\DISPLAY3".to_string()),
+                MenuCommand::ToggleStartup,
+                MenuCommand::Exit,
+            ]
+        );
+        let selected = MenuCommand::SelectMonitor("\\\\.\\DISPLAY2".to_string());
+        assert!(item_with(&menu, &selected).checked);
+        let unselected = MenuCommand::SelectMonitor("\\\\.\\DISPLAY3".to_string());
+        assert!(!item_with(&menu, &unselected).checked);
+        assert!(item_with(&menu, &MenuCommand::ToggleStartup).checked);
+    }
+
+    #[test]
+    fn on_menu_without_timer_presets() {
+        let _language = lock_language("en");
+        let menu = Menu::build(&MenuState {
+            timer_presets: Vec::new(),
+            ..on_state()
+        });
+        assert!(!commands(&menu)
+            .iter()
+            .any(|c| matches!(c, MenuCommand::TimedOff(_))));
+    }
+
+    #[test]
+    fn off_menu() {
+        let _language = lock_language("en");
+        let menu = Menu::build(&off_state());
+        assert_eq!(
+            commands(&menu),
+            vec![
+                MenuCommand::TurnBackOn,
+                MenuCommand::MonitorOn("\\\\.\\DISPLAY2".to_string()),
+                MenuCommand::MonitorOn("\\\\.\\DISPLAY3".to_string()),
+                MenuCommand::ToggleStartup,
+                MenuCommand::Exit,
+            ]
+        );
+        assert_eq!(menu.items[1].label, "Back on in 12 minutes");
+        assert_eq!(menu.items[1].kind, ItemKind::Label);
+    }
+
+    #[test]
+    fn off_menu_with_one_monitor_off() {
+        let _language = lock_language("en");
+        let menu = Menu::build(&MenuState {
+            off: vec![("\\\\.\\DISPLAY2".to_string(), "Dell & Co".to_string())],
+            timer_status: None,
+            ..off_state()
+        });
+        assert_eq!(
+            commands(&menu),
+            vec![
+                MenuCommand::TurnBackOn,
+                MenuCommand::MonitorOff("\\\\.\\DISPLAY3".to_string()),
+                MenuCommand::ToggleStartup,
+                MenuCommand::Exit,
+            ]
+        );
+    }
+
+    #[test]
+    fn status_labels_come_first() {
+        let _language = lock_language("en");
+        let menu = Menu::build(&MenuState {
+            failed_hotkeys: vec!["Ctrl+Alt+T is in use".to_string()],
+            next_event: Some("Next: off at Mon 18:00".to_string()),
+            ..on_state()
+        });
+        assert_eq!(menu.items[0].label, "Ctrl+Alt+T is in use");
+        assert_eq!(menu.items[1].label, "Next: off at Mon 18:00");
+        assert_eq!(menu.items[2].kind, ItemKind::Separator);
+        assert!(menu.items[..2].iter().all(|item| item.disabled));
+
+        let menu = Menu::build(&MenuState {
+            config_error: Some("expected `,` at line 3".to_string()),
+            ..off_state()
+        });
+        assert_eq!(
+            menu.items[0].label,
+            "Settings could not be read and are not saved: expected `,` at line 3"
+        );
+        assert_eq!(menu.command(menu.items[0].id), None);
+        assert_eq!(menu.items[1].kind, ItemKind::Separator);
+    }
+
+    #[test]
+    fn ids_are_unique_and_resolve() {
+        let _language = lock_language("en");
+        for state in [on_state(), off_state()] {
+            let menu = Menu::build(&state);
+            let items = all_items(&menu.items);
+            let mut ids: Vec<u32> = items.iter().map(|item| item.id).collect();
+            ids.sort();
+            ids.dedup();
+            assert_eq!(ids.len(), items.len());
+            assert!(!ids.contains(&0));
+            for item in items {
+                assert_eq!(menu.find(item.id), Some(item));
+            }
+            assert_eq!(menu.find(1000), None);
+            assert_eq!(menu.command(1000), None);
+        }
+    }
+
+    #[test]
+    fn submenu_items_dispatch() {
+        let _language = lock_language("en");
+        let menu = Menu::build(&on_state());
+        let rename = menu
+            .items
+            .iter()
+            .find(|item| item.label == t("menu-rename"))
+            .unwrap();
+        let ItemKind::Submenu(children) = &rename.kind else {
+            panic!("rename is not a submenu");
+        };
+        assert_eq!(children.len(), 3);
+        assert_eq!(
+            menu.command(children[1].id),
+            Some(&MenuCommand::Rename("\\\\.\\DISPLAY2".to_string()))
+        );
+        // The submenu itself is not a command
+        assert_eq!(menu.command(rename.id), None);
+    }
+
+    #[test]
+    fn labels_separators_and_disabled_items_do_not_dispatch() {
+        let _language = lock_language("en");
+        let menu = Menu::build(&off_state());
+        for item in all_items(&menu.items) {
+            if matches!(item.kind, ItemKind::Label | ItemKind::Separator) {
+                assert_eq!(menu.command(item.id), None);
+            }
+        }
+
+        let mut menu = Menu::build(&on_state());
+        let exit = item_with(&menu, &MenuCommand::Exit);
+        assert_eq!(menu.command(exit.id), Some(&MenuCommand::Exit));
+        menu.items
+            .iter_mut()
+            .find(|item| item.id == exit.id)
+            .unwrap()
+            .disabled = true;
+        assert_eq!(menu.command(exit.id), None);
+    }
+
+    #[test]
+    fn monitor_names_are_escaped() {
+        let _language = lock_language("en");
+        let menu = Menu::build(&on_state());
+        let select = MenuCommand::SelectMonitor("\\\\.\\DISPLAY2".to_string());
+        assert_eq!(item_with(&menu, &select).label, "Dell && Co");
+        let rename = MenuCommand::Rename("\\\\.\\DISPLAY2".to_string());
+        assert_eq!(mnemonic(&item_with(&menu, &rename).label), None);
+
+        let menu = Menu::build(&off_state());
+        let on = MenuCommand::MonitorOn("\\\\.\\DISPLAY2".to_string());
+        assert_eq!(item_with(&menu, &on).label, "Turn on Dell && Co");
+    }
+
+    #[test]
+    fn access_keys_are_unique() {
+        let _language = lock_language("en");
+        for state in [on_state(), off_state()] {
+            let mut keys: Vec<char> = Menu::build(&state)
+                .items
+                .iter()
+                .filter_map(|item| mnemonic(&item.label))
+                .map(|c| c.to_ascii_lowercase())
+                .collect();
+            let count = keys.len();
+            keys.sort();
+            keys.dedup();
+            assert_eq!(keys.len(), count);
+        }
+    }
+
+    #[test]
+    fn access_keys_are_unique_in_every_catalog() {
+        let on_menu = [
+            "menu-identify",
+            "menu-turn-off-selected",
+            "menu-turn-off-for",
+            "menu-rename",
+            "menu-start-on-sign-in",
+            "menu-exit",
+        ];
+        let off_menu = ["menu-turn-back-on", "menu-start-on-sign-in", "menu-exit"];
+        for source in [
+            include_str!("../locales/en.ftl"),
+            include_str!("../locales/de.ftl"),
+        ] {
+            let messages = crate::i18n::parse(source);
+            for keys in [&on_menu[..], &off_menu[..]] {
+                let mut access_keys: Vec<char> = keys
+                    .iter()
+                    .map(|key| {
+                        mnemonic(&messages[*key])
+                            .unwrap()
+                            .to_lowercase()
+                            .next()
+                            .unwrap()
+                    })
+                    .collect();
+                access_keys.sort();
+                access_keys.dedup();
+                assert_eq!(access_keys.len(), keys.len(), "{:?}", access_keys);
+            }
+        }
+    }
+
+    #[test]
+    fn escape_and_mnemonic() {
+        assert_eq!(escape("A & B && C"), "A && B &&&& C");
+        assert_eq!(mnemonic("E&xit"), Some('x'));
+        assert_eq!(mnemonic("&Turn back on"), Some('T'));
+        assert_eq!(mnemonic("Dell && Co"), None);
+        assert_eq!(mnemonic("Dell && &Co"), Some('C'));
+        assert_eq!(mnemonic("Trailing &"), None);
+        assert_eq!(mnemonic("No access key"), None);
+    }
+}
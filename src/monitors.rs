@@ -1,18 +1,36 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::sync::Mutex;
+
 use windows::core::PCWSTR;
 use windows::Win32::Devices::Display::{
     DisplayConfigGetDeviceInfo, GetDisplayConfigBufferSizes, QueryDisplayConfig,
-    DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME, DISPLAYCONFIG_DEVICE_INFO_HEADER,
-    DISPLAYCONFIG_MODE_INFO, DISPLAYCONFIG_PATH_INFO, DISPLAYCONFIG_TARGET_DEVICE_NAME,
-    QDC_ONLY_ACTIVE_PATHS,
+    DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME, DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME,
+    DISPLAYCONFIG_DEVICE_INFO_HEADER, DISPLAYCONFIG_MODE_INFO,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EXTERNAL,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_USB_TUNNEL, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DVI,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HD15, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HDMI,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INTERNAL, DISPLAYCONFIG_OUTPUT_TECHNOLOGY_LVDS,
+    DISPLAYCONFIG_OUTPUT_TECHNOLOGY_MIRACAST, DISPLAYCONFIG_PATH_INFO,
+    DISPLAYCONFIG_SOURCE_DEVICE_NAME, DISPLAYCONFIG_TARGET_DEVICE_NAME,
+    DISPLAYCONFIG_VIDEO_OUTPUT_TECHNOLOGY, QDC_ONLY_ACTIVE_PATHS,
 };
-use windows::Win32::Foundation::WIN32_ERROR;
+use windows::Win32::Foundation::{POINTL, WIN32_ERROR};
 use windows::Win32::Graphics::Gdi::{
     ChangeDisplaySettingsExW, EnumDisplayDevicesW, EnumDisplaySettingsW, CDS_TYPE, DEVMODEW,
-    DISPLAY_DEVICEW, DISPLAY_DEVICE_PRIMARY_DEVICE, DISP_CHANGE_SUCCESSFUL, ENUM_CURRENT_SETTINGS,
+    DISPLAY_DEVICEW, DISPLAY_DEVICE_PRIMARY_DEVICE, DISP_CHANGE_SUCCESSFUL, DM_PELSHEIGHT,
+    DM_PELSWIDTH, DM_POSITION, ENUM_CURRENT_SETTINGS,
 };
 
-use crate::config::{save_config, Config};
+use crate::config::{save_config, Config, Hook, OffMode};
+use crate::hooks::{self, HookEvent};
+use crate::i18n::{t, tr};
+use crate::notify::{self, Change};
+use crate::overlay;
+use crate::timer;
+use crate::topology;
 
 pub fn get_monitor_friendly_names() -> HashMap<String, String> {
     let mut names = HashMap::new();
@@ -158,11 +176,11 @@ pub fn get_monitors() -> Vec<(String, String)> {
                         if !ds.is_empty() && ds != "Generic PnP Monitor" {
                             ds
                         } else {
-                            format!("Display {}", display_num)
+                            tr("monitor-fallback-name", &[("number", &display_num)])
                         }
                     }
                 } else {
-                    format!("Display {}", display_num)
+                    tr("monitor-fallback-name", &[("number", &display_num)])
                 };
                 devices.push((name_str, friendly_name_str));
             }
@@ -186,6 +204,142 @@ pub fn get_primary_monitor() -> Option<String> {
     None
 }
 
+/// Mode, position and connector of an active monitor
+#[derive(Clone, Debug, Default)]
+pub struct MonitorDetails {
+    pub width: u32,
+    pub height: u32,
+    pub refresh: u32, // Hz
+    pub x: i32,
+    pub y: i32,
+    pub connector: Option<String>,
+}
+
+impl MonitorDetails {
+    /// e.g. `HDMI, 2560×1440 @ 60 Hz, +2560,+0`, ending in the position relative to the
+    /// primary monitor or the primary badge
+    pub fn describe(&self, is_primary: bool) -> String {
+        let mut parts = Vec::new();
+        if let Some(connector) = &self.connector {
+            parts.push(connector.clone());
+        }
+        parts.push(tr(
+            "monitor-mode",
+            &[
+                ("width", &self.width),
+                ("height", &self.height),
+                ("refresh", &self.refresh),
+            ],
+        ));
+        if is_primary {
+            parts.push(t("monitor-primary"));
+        } else {
+            // The primary monitor is the origin of desktop coordinates
+            parts.push(format!("{:+},{:+}", self.x, self.y));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Mode and position of an active monitor, without the connector
+pub fn current_details(device_name: &str) -> Option<MonitorDetails> {
+    let device_name_wide: Vec<u16> = device_name
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut devmode: DEVMODEW = unsafe { std::mem::zeroed() };
+    devmode.dmSize = std::mem::size_of::<DEVMODEW>() as u16;
+    let found = unsafe {
+        EnumDisplaySettingsW(
+            PCWSTR(device_name_wide.as_ptr()),
+            ENUM_CURRENT_SETTINGS,
+            &mut devmode,
+        )
+    }
+    .as_bool();
+    found.then_some(unsafe {
+        MonitorDetails {
+            width: devmode.dmPelsWidth,
+            height: devmode.dmPelsHeight,
+            refresh: devmode.dmDisplayFrequency,
+            x: devmode.Anonymous1.Anonymous2.dmPosition.x,
+            y: devmode.Anonymous1.Anonymous2.dmPosition.y,
+            connector: None,
+        }
+    })
+}
+
+fn connector_name(technology: DISPLAYCONFIG_VIDEO_OUTPUT_TECHNOLOGY) -> Option<&'static str> {
+    Some(match technology {
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HDMI => "HDMI",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EXTERNAL
+        | DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_EMBEDDED => "DisplayPort",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DISPLAYPORT_USB_TUNNEL => "USB-C",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_DVI => "DVI",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_HD15 => "VGA",
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_INTERNAL | DISPLAYCONFIG_OUTPUT_TECHNOLOGY_LVDS => {
+            "Internal"
+        }
+        DISPLAYCONFIG_OUTPUT_TECHNOLOGY_MIRACAST => "Miracast",
+        _ => return None,
+    })
+}
+
+/// Connector of each active monitor, by device name
+pub fn get_connectors() -> HashMap<String, String> {
+    let mut connectors = HashMap::new();
+    let mut path_count = 0u32;
+    let mut mode_count = 0u32;
+    unsafe {
+        if GetDisplayConfigBufferSizes(QDC_ONLY_ACTIVE_PATHS, &mut path_count, &mut mode_count)
+            != WIN32_ERROR(0)
+        {
+            return connectors;
+        }
+        let mut paths: Vec<DISPLAYCONFIG_PATH_INFO> = vec![std::mem::zeroed(); path_count as usize];
+        let mut modes: Vec<DISPLAYCONFIG_MODE_INFO> = vec![std::mem::zeroed(); mode_count as usize];
+        if QueryDisplayConfig(
+            QDC_ONLY_ACTIVE_PATHS,
+            &mut path_count,
+            paths.as_mut_ptr(),
+            &mut mode_count,
+            modes.as_mut_ptr(),
+            None,
+        ) != WIN32_ERROR(0)
+        {
+            return connectors;
+        }
+
+        for path in &paths[..path_count as usize] {
+            // The source carries the GDI device name, the target the connector
+            let mut source: DISPLAYCONFIG_SOURCE_DEVICE_NAME = std::mem::zeroed();
+            source.header.r#type = DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME;
+            source.header.size = std::mem::size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>() as u32;
+            source.header.adapterId = path.sourceInfo.adapterId;
+            source.header.id = path.sourceInfo.id;
+            let mut target: DISPLAYCONFIG_TARGET_DEVICE_NAME = std::mem::zeroed();
+            target.header.r#type = DISPLAYCONFIG_DEVICE_INFO_GET_TARGET_NAME;
+            target.header.size = std::mem::size_of::<DISPLAYCONFIG_TARGET_DEVICE_NAME>() as u32;
+            target.header.adapterId = path.targetInfo.adapterId;
+            target.header.id = path.targetInfo.id;
+            if DisplayConfigGetDeviceInfo(&mut source.header) != 0
+                || DisplayConfigGetDeviceInfo(&mut target.header) != 0
+            {
+                continue;
+            }
+            let name = &source.viewGdiDeviceName;
+            let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+            if let Some(connector) = connector_name(target.outputTechnology) {
+                connectors.insert(
+                    String::from_utf16_lossy(&name[..len]),
+                    connector.to_string(),
+                );
+            }
+        }
+    }
+    connectors
+}
+
 pub fn disable_monitor(
     device_name: &str,
     saved_modes: &mut HashMap<String, (u32, u32, i32, i32)>,
@@ -253,35 +407,415 @@ pub fn enable_all_monitors() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-pub fn toggle_monitors(config: &mut Config) {
+/// Restores one monitor to the mode it had before it was disabled
+pub fn enable_monitor(
+    device_name: &str,
+    mode: (u32, u32, i32, i32),
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (width, height, x, y) = mode;
     eprintln!(
-        "Left click registered: Trying to {} {:?}",
-        if config.saved_modes.is_empty() {
-            "disable"
-        } else {
-            "enable"
-        },
-        config.secondary_monitors
+        "Re-enabling {} at {}x{} at ({}, {})",
+        device_name, width, height, x, y
     );
-    if config.saved_modes.is_empty() {
-        // disable
-        for monitor in &config.secondary_monitors {
-            if let Err(e) = disable_monitor(monitor, &mut config.saved_modes) {
-                eprintln!("Error disabling {}: {}", monitor, e);
+    let device_name_wide: Vec<u16> = device_name
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut devmode: DEVMODEW = unsafe { std::mem::zeroed() };
+    devmode.dmSize = std::mem::size_of::<DEVMODEW>() as u16;
+    devmode.dmFields = DM_PELSWIDTH | DM_PELSHEIGHT | DM_POSITION;
+    devmode.dmPelsWidth = width;
+    devmode.dmPelsHeight = height;
+    devmode.Anonymous1.Anonymous2.dmPosition = POINTL { x, y };
+    let result = unsafe {
+        ChangeDisplaySettingsExW(
+            PCWSTR(device_name_wide.as_ptr()),
+            Some(&devmode),
+            None,
+            CDS_TYPE(0),
+            None,
+        )
+    };
+    if result != DISP_CHANGE_SUCCESSFUL {
+        return Err(format!("Failed to enable monitor: {:?}", result).into());
+    }
+    Ok(())
+}
+
+/// Display number of a device name like `\\.\DISPLAY2`
+pub fn display_number(device_name: &str) -> Option<u32> {
+    device_name.strip_prefix("\\\\.\\DISPLAY")?.parse().ok()
+}
+
+pub fn device_name(display_number: u32) -> String {
+    format!("\\\\.\\DISPLAY{}", display_number)
+}
+
+/// Name shown for an active monitor: the user's alias, or the friendly name
+pub fn monitor_name(config: &Config, device_name: &str, friendly_name: &str) -> String {
+    config
+        .aliases
+        .get(device_name)
+        .cloned()
+        .unwrap_or_else(|| friendly_name.to_string())
+}
+
+/// Name shown for a monitor, which may be off and no longer enumerated
+pub fn monitor_label(config: &Config, device_name: &str) -> String {
+    config
+        .aliases
+        .get(device_name)
+        .or_else(|| config.monitor_names.get(device_name))
+        .cloned()
+        .unwrap_or_else(|| device_name.trim_start_matches("\\\\.\\").to_string())
+}
+
+fn update_tray_state(config: &Config) {
+    crate::tray::update_tray_icon(config);
+    crate::tray::update_tooltip(config);
+}
+
+/// Covers a monitor with a black window instead of detaching it
+fn cover_monitor(config: &mut Config, device_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let details = current_details(device_name).ok_or("monitor not found")?;
+    let mode = (details.width, details.height, details.x, details.y);
+    overlay::cover(device_name, mode, config.block_cursor)?;
+    config.saved_modes.insert(device_name.to_string(), mode);
+    config.overlay_monitors.push(device_name.to_string());
+    Ok(())
+}
+
+/// A requested change, or a follow-up that needs the state the changes before it left behind
+enum Job {
+    Change(Change, Vec<String>),
+    Then(Box<dyn FnOnce(&mut Config)>),
+}
+
+/// Changes requested while a before hook runs wait here and are applied in order
+struct Queue {
+    jobs: VecDeque<Job>,
+    hook: Option<usize>, // id of the before hook the front job waits for
+    next_id: usize,
+    applying: usize, // nesting depth of `advance`
+}
+
+// Only used from the UI thread
+unsafe impl Send for Queue {}
+
+static QUEUE: Mutex<Queue> = Mutex::new(Queue {
+    jobs: VecDeque::new(),
+    hook: None,
+    next_id: 0,
+    applying: 0,
+});
+
+/// Turns the given monitors off, leaving any other monitor as it is
+pub fn turn_off(config: &mut Config, monitors: &[String]) {
+    request(config, Job::Change(Change::Off, monitors.to_vec()));
+}
+
+/// Turns the given monitors back on. Restoring every disabled monitor resets all displays at once.
+pub fn turn_on(config: &mut Config, monitors: &[String]) {
+    request(config, Job::Change(Change::On, monitors.to_vec()));
+}
+
+/// Runs `f` once the changes requested before it have been applied or cancelled
+pub fn then(config: &mut Config, f: impl FnOnce(&mut Config) + 'static) {
+    request(config, Job::Then(Box::new(f)));
+}
+
+/// Requests `change` and passes `done` the monitors that were off before it, once what it
+/// requested has been applied or cancelled
+pub fn track(
+    config: &mut Config,
+    change: impl FnOnce(&mut Config),
+    done: impl FnOnce(&mut Config, Vec<String>) + 'static,
+) {
+    let before = Rc::new(RefCell::new(Vec::new()));
+    let snapshot = before.clone();
+    then(config, move |config| {
+        *snapshot.borrow_mut() = config.saved_modes.keys().cloned().collect();
+    });
+    change(config);
+    then(config, move |config| done(config, before.take()));
+}
+
+/// True while changes wait for a before hook or are being applied
+pub fn is_busy() -> bool {
+    let queue = QUEUE.lock().unwrap();
+    queue.hook.is_some() || !queue.jobs.is_empty() || queue.applying > 0
+}
+
+fn request(config: &mut Config, job: Job) {
+    QUEUE.lock().unwrap().jobs.push_back(job);
+    advance(config);
+}
+
+fn advance(config: &mut Config) {
+    QUEUE.lock().unwrap().applying += 1;
+    apply_queued(config);
+    QUEUE.lock().unwrap().applying -= 1;
+}
+
+/// Applies queued jobs until one has to wait for its before hook
+fn apply_queued(config: &mut Config) {
+    loop {
+        let job = {
+            let mut queue = QUEUE.lock().unwrap();
+            if queue.hook.is_some() {
+                return;
+            }
+            match queue.jobs.pop_front() {
+                Some(job) => job,
+                None => return,
+            }
+        };
+        let (change, monitors) = match job {
+            Job::Then(f) => {
+                // What it requests goes ahead of the jobs queued after it
+                let rest = std::mem::take(&mut QUEUE.lock().unwrap().jobs);
+                f(config);
+                QUEUE.lock().unwrap().jobs.extend(rest);
+                continue;
             }
+            Job::Change(change, monitors) => (change, pending(config, change, &monitors)),
+        };
+        if monitors.is_empty() {
+            continue;
         }
-    } else {
-        // enable - just reset all displays at once
+        match change {
+            Change::Off => eprintln!("Trying to disable {:?}", monitors),
+            Change::On => eprintln!("Trying to enable {:?}", monitors),
+        }
+        let (hook, event) = before_hook(config, change);
+        let screens_off = change == Change::On;
+
+        // With a window the hook runs on a worker thread and `hook_done` picks the change up
+        let hwnd = unsafe { crate::tray::TRAY_HWND };
+        if hooks::is_set(hook) && !hwnd.is_invalid() {
+            let id = {
+                let mut queue = QUEUE.lock().unwrap();
+                let id = queue.next_id;
+                queue.next_id += 1;
+                queue.hook = Some(id);
+                queue.jobs.push_front(Job::Change(change, monitors.clone()));
+                id
+            };
+            hooks::start(hook, event, &monitors, screens_off, hwnd, id);
+            return;
+        }
+        if hooks::run(hook, event, &monitors, screens_off, None) {
+            apply(config, change, &monitors);
+        } else {
+            eprintln!("Toggle cancelled by {} hook", event.name());
+        }
+    }
+}
+
+/// Called when the before hook `id` started by `advance` has exited
+pub fn hook_done(config: &mut Config, id: usize, allowed: bool) {
+    let job = {
+        let mut queue = QUEUE.lock().unwrap();
+        // Cancelled by restore_all in the meantime
+        if queue.hook != Some(id) {
+            return;
+        }
+        queue.hook = None;
+        queue.jobs.pop_front()
+    };
+    if let Some(Job::Change(change, monitors)) = job {
+        if allowed {
+            let monitors = pending(config, change, &monitors);
+            if !monitors.is_empty() {
+                apply(config, change, &monitors);
+            }
+        } else {
+            eprintln!(
+                "Toggle cancelled by {} hook",
+                before_hook(config, change).1.name()
+            );
+        }
+    }
+    advance(config);
+}
+
+fn before_hook(config: &Config, change: Change) -> (&Option<Hook>, HookEvent) {
+    match change {
+        Change::Off => (&config.hooks.before_off, HookEvent::BeforeOff),
+        Change::On => (&config.hooks.before_on, HookEvent::BeforeOn),
+    }
+}
+
+/// The requested monitors the change would still affect
+fn pending(config: &Config, change: Change, monitors: &[String]) -> Vec<String> {
+    match change {
+        Change::Off => {
+            let primary = get_primary_monitor();
+            monitors
+                .iter()
+                .filter(|m| !config.saved_modes.contains_key(*m) && Some(*m) != primary.as_ref())
+                .cloned()
+                .collect()
+        }
+        Change::On => monitors
+            .iter()
+            .filter(|m| config.saved_modes.contains_key(*m))
+            .cloned()
+            .collect(),
+    }
+}
+
+fn apply(config: &mut Config, change: Change, monitors: &[String]) {
+    match change {
+        Change::Off => disable(config, monitors),
+        Change::On => enable(config, monitors),
+    }
+}
+
+fn disable(config: &mut Config, monitors: &[String]) {
+    // Remember names, disabled monitors can't be enumerated
+    for (monitor, friendly_name) in get_monitors() {
+        config.monitor_names.insert(monitor, friendly_name);
+    }
+
+    let mut errors = Vec::new();
+    for monitor in monitors {
+        let result = match config.off_mode {
+            OffMode::Disable => disable_monitor(monitor, &mut config.saved_modes),
+            OffMode::Overlay => cover_monitor(config, monitor),
+        };
+        if let Err(e) = result {
+            eprintln!("Error disabling {}: {}", monitor, e);
+            errors.push(format!("{}: {}", monitor, e));
+            config.saved_modes.remove(monitor);
+        }
+    }
+    hooks::run(
+        &config.hooks.after_off,
+        HookEvent::AfterOff,
+        monitors,
+        true,
+        None,
+    );
+    finish(config, Change::Off, monitors, errors);
+}
+
+fn enable(config: &mut Config, monitors: &[String]) {
+    // Covered monitors only lose their overlay
+    let (covered, disabled): (Vec<String>, Vec<String>) = monitors
+        .iter()
+        .cloned()
+        .partition(|m| config.overlay_monitors.contains(m));
+    for monitor in &covered {
+        overlay::uncover(monitor);
+        config.saved_modes.remove(monitor);
+    }
+    config.overlay_monitors.retain(|m| !covered.contains(m));
+
+    let mut errors = Vec::new();
+    let disabled_count = config
+        .saved_modes
+        .keys()
+        .filter(|m| !config.overlay_monitors.contains(*m))
+        .count();
+    if !disabled.is_empty() && disabled.len() == disabled_count {
         if let Err(e) = enable_all_monitors() {
             eprintln!("Error enabling monitors: {}", e);
+            errors.push(e.to_string());
         }
-        config.saved_modes.clear();
+        let overlay_monitors = &config.overlay_monitors;
+        config
+            .saved_modes
+            .retain(|m, _| overlay_monitors.contains(m));
+    } else {
+        for monitor in &disabled {
+            let mode = config.saved_modes[monitor];
+            match enable_monitor(monitor, mode) {
+                Ok(()) => {
+                    config.saved_modes.remove(monitor);
+                }
+                Err(e) => {
+                    eprintln!("Error enabling {}: {}", monitor, e);
+                    errors.push(format!("{}: {}", monitor, e));
+                }
+            }
+        }
+    }
+    hooks::run(
+        &config.hooks.after_on,
+        HookEvent::AfterOn,
+        monitors,
+        !config.saved_modes.is_empty(),
+        None,
+    );
+    finish(config, Change::On, monitors, errors);
+}
+
+fn finish(config: &mut Config, change: Change, monitors: &[String], errors: Vec<String>) {
+    topology::invalidate();
+    if !errors.is_empty() {
+        hooks::run(
+            &config.hooks.on_error,
+            HookEvent::Error,
+            monitors,
+            !config.saved_modes.is_empty(),
+            Some(&errors.join("; ")),
+        );
     }
+    timer::forget_restored(config);
     save_config(config);
-    crate::tray::update_tray_icon(if config.saved_modes.is_empty() {
-        crate::tray::IDI_SCREEN_ON
-    } else {
-        crate::tray::IDI_SCREEN_OFF
+    update_tray_state(config);
+    notify::report(config, change, monitors, &errors);
+}
+
+/// Turns every display back on from its registry settings, even when `saved_modes` was lost
+pub fn restore_all(config: &mut Config) {
+    // Drop changes still waiting for a hook, follow-ups run against the restored state
+    {
+        let mut queue = QUEUE.lock().unwrap();
+        queue.hook = None;
+        queue.jobs.retain(|job| matches!(job, Job::Then(_)));
+    }
+    let monitors: Vec<String> = config.saved_modes.keys().cloned().collect();
+    let mut errors = Vec::new();
+    for monitor in config.overlay_monitors.drain(..) {
+        overlay::uncover(&monitor);
+        config.saved_modes.remove(&monitor);
+    }
+    match enable_all_monitors() {
+        Ok(()) => config.saved_modes.clear(),
+        // Still off, keep what is needed to restore them later
+        Err(e) => {
+            eprintln!("{}", e);
+            errors.push(e.to_string());
+        }
+    }
+    finish(config, Change::On, &monitors, errors);
+    advance(config);
+}
+
+pub fn toggle_monitors(config: &mut Config) {
+    // Decided once the changes before it are done
+    then(config, |config| {
+        if config.saved_modes.is_empty() {
+            let monitors = config.secondary_monitors.clone();
+            turn_off(config, &monitors);
+        } else {
+            let monitors: Vec<String> = config.saved_modes.keys().cloned().collect();
+            turn_on(config, &monitors);
+        }
+    });
+}
+
+/// Turns a single monitor off, or back on if it is already off
+pub fn toggle_monitor(config: &mut Config, device_name: &str) {
+    let monitors = [device_name.to_string()];
+    then(config, move |config| {
+        if config.saved_modes.contains_key(&monitors[0]) {
+            turn_on(config, &monitors);
+        } else {
+            turn_off(config, &monitors);
+        }
     });
 }
 
@@ -0,0 +1,79 @@
+use std::sync::Mutex;
+
+use windows::Win32::UI::Shell::{NIIF_ERROR, NIIF_INFO, NIIF_WARNING};
+
+use crate::a11y;
+use crate::config::Config;
+use crate::i18n::{t, tr};
+use crate::monitors::{monitor_label, turn_off, turn_on};
+use crate::tray::show_balloon;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Change {
+    Off,
+    On,
+}
+
+// The change the last notification offers to undo, with the monitors it succeeded for
+static UNDO: Mutex<Option<(Change, Vec<String>)>> = Mutex::new(None);
+
+/// Tells the user how turning `monitors` off or on went, when enabled for the outcome.
+/// Falls back to the screen reader announcement otherwise.
+pub fn report(config: &Config, change: Change, monitors: &[String], errors: &[String]) {
+    let changed: Vec<String> = monitors
+        .iter()
+        .filter(|m| config.saved_modes.contains_key(*m) == (change == Change::Off))
+        .cloned()
+        .collect();
+    let labels = || {
+        changed
+            .iter()
+            .map(|m| monitor_label(config, m))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let (enabled, flags, text) = if errors.is_empty() {
+        let key = match change {
+            Change::Off => "notify-off",
+            Change::On => "notify-on",
+        };
+        let text = tr(key, &[("monitors", &labels())]);
+        (config.notifications.success, NIIF_INFO, text)
+    } else if !changed.is_empty() {
+        let text = tr(
+            "notify-partial",
+            &[("monitors", &labels()), ("errors", &errors.join("\n"))],
+        );
+        (config.notifications.partial_failure, NIIF_WARNING, text)
+    } else {
+        let text = tr("notify-error", &[("errors", &errors.join("\n"))]);
+        (config.notifications.error, NIIF_ERROR, text)
+    };
+
+    if !enabled {
+        *UNDO.lock().unwrap() = None;
+        a11y::announce(config);
+        return;
+    }
+    let text = if changed.is_empty() {
+        *UNDO.lock().unwrap() = None;
+        text
+    } else {
+        *UNDO.lock().unwrap() = Some((change, changed));
+        format!("{}\n{}", text, t("notify-undo"))
+    };
+    show_balloon(&t("app-name"), &text, flags);
+}
+
+/// Reverts the change of the last notification, when it is clicked
+pub fn undo(config: &mut Config) {
+    let Some((change, monitors)) = UNDO.lock().unwrap().take() else {
+        return;
+    };
+    eprintln!("Undoing the last change");
+    match change {
+        Change::Off => turn_on(config, &monitors),
+        Change::On => turn_off(config, &monitors),
+    }
+}
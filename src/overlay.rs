@@ -0,0 +1,173 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, Once};
+
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::Graphics::Gdi::{GetStockObject, BLACK_BRUSH, HBRUSH};
+use windows::Win32::UI::WindowsAndMessaging::{
+    ClipCursor, CreateWindowExW, DefWindowProcW, DestroyWindow, GetSystemMetrics, KillTimer,
+    RegisterClassW, SetCursor, SetTimer, SM_CXSCREEN, SM_CYSCREEN, WM_SETCURSOR, WM_TIMER,
+    WNDCLASSW, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP, WS_VISIBLE,
+};
+
+use crate::config::Config;
+use crate::tray::{HINSTANCE, TRAY_HWND};
+
+const WINDOW_CLASS: PCWSTR = w!("ScreenOffOverlayClass");
+
+// Windows drops the cursor clip on some events, such as Ctrl+Alt+Del, so it is renewed
+const CLIP_TIMER_ID: usize = 1;
+const CLIP_MS: u32 = 1000;
+
+/// A black window covering a monitor
+struct Overlay {
+    monitor: String, // device name
+    hwnd: HWND,
+}
+
+// The window handles are only used from the UI thread
+unsafe impl Send for Overlay {}
+
+static OVERLAYS: Mutex<Vec<Overlay>> = Mutex::new(Vec::new());
+
+static BLOCK_CURSOR: AtomicBool = AtomicBool::new(false);
+
+// Whether the cursor clip is ours to release
+static CLIPPED: AtomicBool = AtomicBool::new(false);
+
+static REGISTER_CLASS: Once = Once::new();
+
+/// Covers a monitor at `(width, height, x, y)` with a topmost black window that takes
+/// all clicks, keeping the cursor on the primary monitor when `block_cursor` is set
+pub fn cover(
+    monitor: &str,
+    (width, height, x, y): (u32, u32, i32, i32),
+    block_cursor: bool,
+) -> Result<(), String> {
+    // Overlays live as long as the app, a command line run without it can't keep them
+    if unsafe { TRAY_HWND }.is_invalid() {
+        return Err("covering monitors needs ScreenOff running".to_string());
+    }
+    let hinstance = unsafe { HINSTANCE };
+    REGISTER_CLASS.call_once(|| {
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: hinstance,
+            lpszClassName: WINDOW_CLASS,
+            hbrBackground: HBRUSH(unsafe { GetStockObject(BLACK_BRUSH) }.0),
+            ..Default::default()
+        };
+        unsafe { RegisterClassW(&class) };
+    });
+
+    uncover(monitor);
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE,
+            WINDOW_CLASS,
+            PCWSTR::null(),
+            WS_POPUP | WS_VISIBLE,
+            x,
+            y,
+            width as i32,
+            height as i32,
+            None,
+            None,
+            Some(hinstance),
+            None,
+        )
+    }
+    .map_err(|e| e.to_string())?;
+    eprintln!(
+        "Covering {} ({}x{} at ({}, {}))",
+        monitor, width, height, x, y
+    );
+    OVERLAYS.lock().unwrap().push(Overlay {
+        monitor: monitor.to_string(),
+        hwnd,
+    });
+    BLOCK_CURSOR.store(block_cursor, Ordering::SeqCst);
+    unsafe { SetTimer(Some(hwnd), CLIP_TIMER_ID, CLIP_MS, None) };
+    clip_cursor();
+    Ok(())
+}
+
+/// Removes the overlay of a monitor, if it has one
+pub fn uncover(monitor: &str) {
+    let removed: Vec<Overlay> = {
+        let mut overlays = OVERLAYS.lock().unwrap();
+        let (removed, kept) = std::mem::take(&mut *overlays)
+            .into_iter()
+            .partition(|o| o.monitor == monitor);
+        *overlays = kept;
+        removed
+    };
+    for overlay in removed {
+        eprintln!("Uncovering {}", overlay.monitor);
+        let _ = unsafe { KillTimer(Some(overlay.hwnd), CLIP_TIMER_ID) };
+        let _ = unsafe { DestroyWindow(overlay.hwnd) };
+    }
+    clip_cursor();
+}
+
+/// Removes every overlay without changing the config, when the app exits
+pub fn release() {
+    let overlays = std::mem::take(&mut *OVERLAYS.lock().unwrap());
+    for overlay in overlays {
+        let _ = unsafe { DestroyWindow(overlay.hwnd) };
+    }
+    clip_cursor();
+}
+
+/// Covers the monitors that were covered when the app last exited
+pub fn restore(config: &mut Config) {
+    let monitors = std::mem::take(&mut config.overlay_monitors);
+    for monitor in monitors {
+        let Some(&mode) = config.saved_modes.get(&monitor) else {
+            continue;
+        };
+        match cover(&monitor, mode, config.block_cursor) {
+            Ok(()) => config.overlay_monitors.push(monitor),
+            Err(e) => {
+                eprintln!("Error covering {}: {}", monitor, e);
+                config.saved_modes.remove(&monitor);
+            }
+        }
+    }
+}
+
+/// Keeps the cursor on the primary monitor while monitors are covered and blocking is on
+fn clip_cursor() {
+    let active = BLOCK_CURSOR.load(Ordering::SeqCst) && !OVERLAYS.lock().unwrap().is_empty();
+    if active {
+        // The primary monitor is at the origin and is never covered
+        let primary = RECT {
+            left: 0,
+            top: 0,
+            right: unsafe { GetSystemMetrics(SM_CXSCREEN) },
+            bottom: unsafe { GetSystemMetrics(SM_CYSCREEN) },
+        };
+        let _ = unsafe { ClipCursor(Some(&primary)) };
+        CLIPPED.store(true, Ordering::SeqCst);
+    } else if CLIPPED.swap(false, Ordering::SeqCst) {
+        let _ = unsafe { ClipCursor(None) };
+    }
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        // No cursor over a covered monitor
+        WM_SETCURSOR => {
+            unsafe { SetCursor(None) };
+            return LRESULT(1);
+        }
+        WM_TIMER if wparam.0 == CLIP_TIMER_ID => clip_cursor(),
+        _ => return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+    LRESULT(0)
+}
@@ -0,0 +1,82 @@
+use std::sync::Mutex;
+
+use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+use crate::config::Config;
+use crate::monitors::{then, track, turn_off, turn_on};
+
+struct PowerState {
+    on_ac: Option<bool>,
+    turned_off: Vec<String>, // monitors turned off for running on battery
+}
+
+static STATE: Mutex<PowerState> = Mutex::new(PowerState {
+    on_ac: None,
+    turned_off: Vec::new(),
+});
+
+/// Whether the computer runs on AC power, `None` if unknown (e.g. desktops)
+fn on_ac() -> Option<bool> {
+    let mut status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&mut status) }.ok()?;
+    match status.ACLineStatus {
+        0 => Some(false),
+        1 => Some(true),
+        _ => None,
+    }
+}
+
+/// Applies the power policy for the current power source
+pub fn start(config: &mut Config) {
+    if config.power.enabled {
+        eprintln!("Power policy enabled");
+        handle_power_change(config);
+    }
+}
+
+/// Handles `PBT_APMPOWERSTATUSCHANGE`, which is also sent for battery level changes
+pub fn handle_power_change(config: &mut Config) {
+    if !config.power.enabled {
+        return;
+    }
+    let Some(ac) = on_ac() else {
+        return;
+    };
+    {
+        let mut state = STATE.lock().unwrap();
+        if state.on_ac == Some(ac) {
+            return;
+        }
+        state.on_ac = Some(ac);
+    }
+
+    if ac {
+        // Taken once a change still waiting for a hook has been recorded
+        then(config, |config| {
+            let monitors = std::mem::take(&mut STATE.lock().unwrap().turned_off);
+            if config.power.restore_on_ac && !monitors.is_empty() {
+                eprintln!("Back on AC power, turning monitors back on");
+                turn_on(config, &monitors);
+            }
+        });
+    } else {
+        let monitors = if config.power.monitors.is_empty() {
+            config.secondary_monitors.clone()
+        } else {
+            config.power.monitors.clone()
+        };
+        eprintln!("Running on battery, turning off {:?}", monitors);
+        let requested = monitors.clone();
+        track(
+            config,
+            |config| turn_off(config, &requested),
+            // Only restore what this policy turned off
+            move |config, already_off| {
+                STATE.lock().unwrap().turned_off = monitors
+                    .into_iter()
+                    .filter(|m| config.saved_modes.contains_key(m) && !already_off.contains(m))
+                    .collect();
+            },
+        );
+    }
+}
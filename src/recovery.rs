@@ -0,0 +1,137 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use windows::core::{BOOL, PCWSTR};
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::System::Console::{
+    SetConsoleCtrlHandler, CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT, CTRL_C_EVENT,
+};
+use windows::Win32::UI::Input::KeyboardAndMouse::UnregisterHotKey;
+use windows::Win32::UI::WindowsAndMessaging::{
+    MessageBoxW, PostMessageW, IDYES, MB_ICONWARNING, MB_TOPMOST, MB_YESNO, WM_CLOSE,
+};
+
+use crate::config::{save_config, Config, ExitPolicy};
+use crate::hotkeys::{self, parse_hotkey};
+use crate::i18n::t;
+use crate::monitors::restore_all;
+use crate::tray::TRAY_HWND;
+
+/// Id of the restore-everything hotkey, outside the range used by configured hotkeys
+pub const RESTORE_HOTKEY_ID: i32 = 0x2000;
+
+pub const DEFAULT_RESTORE_HOTKEY: &str = "Ctrl+Alt+Shift+R";
+
+// Set once the app has cleaned up, so a console close can wait for it
+static EXITED: AtomicBool = AtomicBool::new(false);
+
+// Windows ends the process a few seconds after a console close event
+const CLOSE_WAIT: Duration = Duration::from_secs(4);
+
+fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+pub enum ExitReason {
+    Quit,
+    SessionEnd,
+}
+
+/// Offers to turn the monitors back on when the last run ended while they were off,
+/// or does so right away when not `interactive`, then marks the app as running
+pub fn check_unclean_shutdown(config: &mut Config, interactive: bool) {
+    if config.running && !config.saved_modes.is_empty() {
+        eprintln!("ScreenOff did not exit cleanly while monitors were off");
+        let answer = if !interactive {
+            IDYES
+        } else {
+            let text = wide(&t("recovery-prompt"));
+            let caption = wide(&t("app-name"));
+            unsafe {
+                MessageBoxW(
+                    None,
+                    PCWSTR(text.as_ptr()),
+                    PCWSTR(caption.as_ptr()),
+                    MB_YESNO | MB_ICONWARNING | MB_TOPMOST,
+                )
+            }
+        };
+        if answer == IDYES {
+            restore_all(config);
+        }
+    }
+    config.running = true;
+    save_config(config);
+}
+
+/// Applies the exit policy and marks the app as cleanly stopped, unless monitors the
+/// policy should have restored are still off
+pub fn on_exit(config: &mut Config, reason: ExitReason) {
+    // Session end may already have handled it
+    if EXITED.load(Ordering::SeqCst) {
+        return;
+    }
+    let restore = matches!(
+        (config.on_exit, reason),
+        (ExitPolicy::RestoreOnQuit, _) | (ExitPolicy::RestoreOnSessionEnd, ExitReason::SessionEnd)
+    );
+    if restore && !config.saved_modes.is_empty() {
+        // Without hooks, which could veto it
+        eprintln!("Exiting, turning monitors back on");
+        restore_all(config);
+    }
+    // Otherwise the next start offers the restore again
+    if !restore || config.saved_modes.is_empty() {
+        config.running = false;
+    }
+    save_config(config);
+    EXITED.store(true, Ordering::SeqCst);
+}
+
+/// Registers the restore-everything hotkey, falling back to the default when the
+/// configured one is invalid
+pub fn register_hotkey(hwnd: HWND, config: &Config) {
+    let chord = parse_hotkey(&config.restore_hotkey).unwrap_or_else(|e| {
+        eprintln!(
+            "Invalid restore hotkey \"{}\": {}, using {}",
+            config.restore_hotkey, e, DEFAULT_RESTORE_HOTKEY
+        );
+        parse_hotkey(DEFAULT_RESTORE_HOTKEY).unwrap()
+    });
+    if hotkeys::register(hwnd, RESTORE_HOTKEY_ID, chord) {
+        eprintln!("Restore hotkey {} registered successfully", chord);
+    } else {
+        eprintln!("Failed to register restore hotkey {}", chord);
+    }
+}
+
+pub fn unregister_hotkey(hwnd: HWND) {
+    let _ = unsafe { UnregisterHotKey(Some(hwnd), RESTORE_HOTKEY_ID) };
+}
+
+/// Lets Ctrl+C and closing the console window exit through the normal cleanup
+pub fn install_ctrl_handler() {
+    if let Err(e) = unsafe { SetConsoleCtrlHandler(Some(ctrl_handler), true) } {
+        eprintln!("Failed to install console handler: {}", e);
+    }
+}
+
+unsafe extern "system" fn ctrl_handler(ctrl_type: u32) -> BOOL {
+    match ctrl_type {
+        CTRL_C_EVENT | CTRL_BREAK_EVENT | CTRL_CLOSE_EVENT => {
+            let hwnd = unsafe { TRAY_HWND };
+            if hwnd.is_invalid() {
+                return false.into();
+            }
+            let _ = unsafe { PostMessageW(Some(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0)) };
+            // Returning lets Windows end the process, so wait for the cleanup
+            let start = Instant::now();
+            while !EXITED.load(Ordering::SeqCst) && start.elapsed() < CLOSE_WAIT {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            true.into()
+        }
+        // Logoff and shutdown arrive as WM_ENDSESSION
+        _ => false.into(),
+    }
+}
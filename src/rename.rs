@@ -0,0 +1,211 @@
+use std::sync::{Mutex, Once};
+
+use windows::core::{w, PCWSTR};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::Graphics::Gdi::{GetStockObject, DEFAULT_GUI_FONT};
+use windows::Win32::UI::Controls::EM_SETSEL;
+use windows::Win32::UI::Input::KeyboardAndMouse::SetFocus;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, GetDlgItem, GetSystemMetrics,
+    GetWindowTextLengthW, GetWindowTextW, IsDialogMessageW, RegisterClassW, SendMessageW,
+    SetForegroundWindow, BS_DEFPUSHBUTTON, BS_PUSHBUTTON, ES_AUTOHSCROLL, HMENU, IDCANCEL, IDOK,
+    MSG, SM_CXSCREEN, SM_CYSCREEN, WINDOW_STYLE, WM_CLOSE, WM_COMMAND, WM_SETFONT, WNDCLASSW,
+    WS_BORDER, WS_CAPTION, WS_CHILD, WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP, WS_SYSMENU,
+    WS_TABSTOP, WS_VISIBLE,
+};
+
+use crate::config::{save_config, Config};
+use crate::i18n::{t, tr};
+use crate::monitors::monitor_label;
+use crate::tray::{update_tooltip, CONFIG, HINSTANCE};
+
+const WINDOW_CLASS: PCWSTR = w!("ScreenOffRenameClass");
+
+const TEXT_ID: i32 = 100;
+const EDIT_ID: i32 = 101;
+
+const WIDTH: i32 = 360;
+const HEIGHT: i32 = 160;
+
+/// The open dialog and the monitor it renames
+struct Dialog {
+    hwnd: HWND,
+    monitor: String, // device name
+}
+
+// The window handle is only used from the UI thread
+unsafe impl Send for Dialog {}
+
+static DIALOG: Mutex<Option<Dialog>> = Mutex::new(None);
+
+static REGISTER_CLASS: Once = Once::new();
+
+fn wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// Asks for a new name for `monitor`, replacing a dialog that is already open
+pub fn open(config: &Config, monitor: &str) {
+    close();
+    let current = monitor_label(config, monitor);
+    let Some(hwnd) = create_window(&current) else {
+        return;
+    };
+    *DIALOG.lock().unwrap() = Some(Dialog {
+        hwnd,
+        monitor: monitor.to_string(),
+    });
+}
+
+/// Lets Tab, Enter and Escape work in the dialog. True when the message was handled.
+pub fn is_dialog_message(msg: &MSG) -> bool {
+    let hwnd = match DIALOG.lock().unwrap().as_ref() {
+        Some(dialog) => dialog.hwnd,
+        None => return false,
+    };
+    unsafe { IsDialogMessageW(hwnd, msg) }.as_bool()
+}
+
+fn create_window(current: &str) -> Option<HWND> {
+    let hinstance = unsafe { HINSTANCE };
+    REGISTER_CLASS.call_once(|| {
+        let class = WNDCLASSW {
+            lpfnWndProc: Some(window_proc),
+            hInstance: hinstance,
+            lpszClassName: WINDOW_CLASS,
+            ..Default::default()
+        };
+        unsafe { RegisterClassW(&class) };
+    });
+
+    let title = wide(&t("app-name"));
+    let x = (unsafe { GetSystemMetrics(SM_CXSCREEN) } - WIDTH) / 2;
+    let y = (unsafe { GetSystemMetrics(SM_CYSCREEN) } - HEIGHT) / 2;
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
+            WINDOW_CLASS,
+            PCWSTR(title.as_ptr()),
+            WS_POPUP | WS_CAPTION | WS_SYSMENU | WS_VISIBLE,
+            x,
+            y,
+            WIDTH,
+            HEIGHT,
+            None,
+            None,
+            Some(hinstance),
+            None,
+        )
+    }
+    .ok()?;
+
+    let controls = [
+        (
+            w!("STATIC"),
+            wide(&tr("rename-prompt", &[("name", &current)])),
+            WINDOW_STYLE(0),
+            TEXT_ID as usize,
+            (16, 12, 320, 36),
+        ),
+        (
+            w!("EDIT"),
+            wide(current),
+            WS_BORDER | WS_TABSTOP | WINDOW_STYLE(ES_AUTOHSCROLL as u32),
+            EDIT_ID as usize,
+            (16, 52, 320, 24),
+        ),
+        (
+            w!("BUTTON"),
+            wide(&t("rename-ok")),
+            WINDOW_STYLE(BS_DEFPUSHBUTTON as u32) | WS_TABSTOP,
+            IDOK.0 as usize,
+            (96, 86, 116, 28),
+        ),
+        (
+            w!("BUTTON"),
+            wide(&t("rename-cancel")),
+            WINDOW_STYLE(BS_PUSHBUTTON as u32) | WS_TABSTOP,
+            IDCANCEL.0 as usize,
+            (220, 86, 116, 28),
+        ),
+    ];
+    let font = unsafe { GetStockObject(DEFAULT_GUI_FONT) };
+    for (class, text, style, id, (x, y, width, height)) in controls {
+        if let Ok(control) = unsafe {
+            CreateWindowExW(
+                Default::default(),
+                class,
+                PCWSTR(text.as_ptr()),
+                WS_CHILD | WS_VISIBLE | style,
+                x,
+                y,
+                width,
+                height,
+                Some(hwnd),
+                Some(HMENU(id as *mut _)),
+                Some(hinstance),
+                None,
+            )
+        } {
+            unsafe { SendMessageW(control, WM_SETFONT, Some(WPARAM(font.0 as usize)), None) };
+        }
+    }
+    let _ = unsafe { SetForegroundWindow(hwnd) };
+    if let Ok(edit) = unsafe { GetDlgItem(Some(hwnd), EDIT_ID) } {
+        let _ = unsafe { SetFocus(Some(edit)) };
+        unsafe { SendMessageW(edit, EM_SETSEL, Some(WPARAM(0)), Some(LPARAM(-1))) };
+    }
+    Some(hwnd)
+}
+
+fn close() {
+    if let Some(dialog) = DIALOG.lock().unwrap().take() {
+        let _ = unsafe { DestroyWindow(dialog.hwnd) };
+    }
+}
+
+/// Stores the entered name as the monitor's alias, or removes the alias when empty
+fn save() {
+    let Some(dialog) = DIALOG.lock().unwrap().take() else {
+        return;
+    };
+    let mut name = String::new();
+    if let Ok(edit) = unsafe { GetDlgItem(Some(dialog.hwnd), EDIT_ID) } {
+        let mut buffer = vec![0u16; unsafe { GetWindowTextLengthW(edit) } as usize + 1];
+        let len = unsafe { GetWindowTextW(edit, &mut buffer) };
+        name = String::from_utf16_lossy(&buffer[..len as usize])
+            .trim()
+            .to_string();
+    }
+    let _ = unsafe { DestroyWindow(dialog.hwnd) };
+    if unsafe { CONFIG.is_null() } {
+        return;
+    }
+
+    let config = unsafe { &mut *CONFIG };
+    if name.is_empty() {
+        config.aliases.remove(&dialog.monitor);
+    } else {
+        config.aliases.insert(dialog.monitor.clone(), name);
+    }
+    save_config(config);
+    update_tooltip(config);
+}
+
+unsafe extern "system" fn window_proc(
+    hwnd: HWND,
+    msg: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_COMMAND => match (wparam.0 & 0xFFFF) as i32 {
+            id if id == IDOK.0 => save(),
+            id if id == IDCANCEL.0 => close(),
+            _ => {}
+        },
+        WM_CLOSE => close(),
+        _ => return unsafe { DefWindowProcW(hwnd, msg, wparam, lparam) },
+    }
+    LRESULT(0)
+}
@@ -0,0 +1,423 @@
+use std::fmt;
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const FULL_DAY_NAMES: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// A local wall-clock time, to the minute
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocalTime {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+}
+
+/// When a rule fires: a set of weekdays (Monday first) and a minute of the day
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub days: [bool; 7],
+    pub minute_of_day: u32,
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year } as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+    (year, month, day)
+}
+
+impl LocalTime {
+    /// Minutes since 1970-01-01 00:00 on the wall clock, ignoring time zones
+    pub fn to_minutes(self) -> i64 {
+        days_from_civil(self.year, self.month, self.day) * 1440
+            + self.hour as i64 * 60
+            + self.minute as i64
+    }
+
+    pub fn from_minutes(minutes: i64) -> Self {
+        let (year, month, day) = civil_from_days(minutes.div_euclid(1440));
+        let minute_of_day = minutes.rem_euclid(1440) as u32;
+        LocalTime {
+            year,
+            month,
+            day,
+            hour: minute_of_day / 60,
+            minute: minute_of_day % 60,
+        }
+    }
+
+    /// 0 for Monday through 6 for Sunday
+    pub fn weekday(self) -> usize {
+        // 1970-01-01 was a Thursday
+        (days_from_civil(self.year, self.month, self.day) + 3).rem_euclid(7) as usize
+    }
+}
+
+impl fmt::Display for LocalTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let day = DAY_NAMES[self.weekday()];
+        let mut chars = day.chars();
+        let first = chars.next().unwrap_or_default().to_ascii_uppercase();
+        write!(
+            f,
+            "{}{} {:02}:{:02}",
+            first,
+            chars.as_str(),
+            self.hour,
+            self.minute
+        )
+    }
+}
+
+fn parse_day(name: &str) -> Result<usize, String> {
+    let name = name.trim().to_ascii_lowercase();
+    (0..7)
+        .find(|&i| name == DAY_NAMES[i] || name == FULL_DAY_NAMES[i])
+        .ok_or_else(|| format!("unknown day '{}'", name))
+}
+
+/// Parses days like `mon-fri`, `sat,sun`, `weekdays`, `weekends` or `daily`
+pub fn parse_days(text: &str) -> Result<[bool; 7], String> {
+    let mut days = [false; 7];
+    for part in text.split(',') {
+        match part.trim().to_ascii_lowercase().as_str() {
+            "daily" | "every day" | "*" => days = [true; 7],
+            "weekdays" => days[..5].fill(true),
+            "weekends" => days[5..].fill(true),
+            range => {
+                let (start, end) = match range.split_once('-') {
+                    Some((start, end)) => (parse_day(start)?, parse_day(end)?),
+                    None => (parse_day(range)?, parse_day(range)?),
+                };
+                // Ranges may wrap around the week, e.g. `fri-mon`
+                let mut day = start;
+                loop {
+                    days[day] = true;
+                    if day == end {
+                        break;
+                    }
+                    day = (day + 1) % 7;
+                }
+            }
+        }
+    }
+    Ok(days)
+}
+
+/// Parses a 24-hour time like `08:30` into a minute of the day
+pub fn parse_time(text: &str) -> Result<u32, String> {
+    let invalid = || format!("invalid time '{}', expected HH:MM", text);
+    let (hour, minute) = text.trim().split_once(':').ok_or_else(invalid)?;
+    let hour: u32 = hour.parse().map_err(|_| invalid())?;
+    let minute: u32 = minute.parse().map_err(|_| invalid())?;
+    if hour > 23 || minute > 59 {
+        return Err(invalid());
+    }
+    Ok(hour * 60 + minute)
+}
+
+pub fn parse_rule(days: &str, at: &str) -> Result<Rule, String> {
+    Ok(Rule {
+        days: parse_days(days)?,
+        minute_of_day: parse_time(at)?,
+    })
+}
+
+/// Trigger times of a rule on the days in `first_day..=last_day`, in minutes
+fn triggers(rule: &Rule, first_day: i64, last_day: i64) -> impl Iterator<Item = i64> + '_ {
+    (first_day..=last_day)
+        .filter(|day| rule.days[(day + 3).rem_euclid(7) as usize])
+        .map(|day| day * 1440 + rule.minute_of_day as i64)
+}
+
+/// The rule that fired last in `(since, now]`, if any.
+/// When several triggers were missed, e.g. during sleep, only the latest one counts.
+pub fn due(rules: &[Rule], since: i64, now: i64) -> Option<usize> {
+    if now <= since {
+        return None;
+    }
+    // Every rule repeats within a week
+    let first_day = since.max(now - 7 * 1440).div_euclid(1440);
+    let last_day = now.div_euclid(1440);
+    rules
+        .iter()
+        .enumerate()
+        .filter_map(|(i, rule)| {
+            triggers(rule, first_day, last_day)
+                .filter(|t| *t > since && *t <= now)
+                .max()
+                .map(|t| (t, i))
+        })
+        .max()
+        .map(|(_, i)| i)
+}
+
+/// The next rule to fire after `now` and when
+pub fn next(rules: &[Rule], now: i64) -> Option<(usize, LocalTime)> {
+    let today = now.div_euclid(1440);
+    rules
+        .iter()
+        .enumerate()
+        .filter_map(|(i, rule)| {
+            triggers(rule, today, today + 7)
+                .find(|t| *t > now)
+                .map(|t| (t, i))
+        })
+        .min()
+        .map(|(t, i)| (i, LocalTime::from_minutes(t)))
+}
+
+/// Tracks how far the schedule has been evaluated.
+/// When the clock falls back for daylight saving time, triggers in the repeated hour
+/// have already fired, so evaluation waits until the wall clock passes the old time again.
+pub struct Progress {
+    evaluated: i64,
+}
+
+// Larger backward jumps are clock changes rather than daylight saving time
+const MAX_FALL_BACK: i64 = 120;
+
+impl Progress {
+    pub fn new(now: i64) -> Self {
+        Progress { evaluated: now }
+    }
+
+    /// Advances to `now` and returns the rule to apply, if any
+    pub fn advance(&mut self, rules: &[Rule], now: i64) -> Option<usize> {
+        if now < self.evaluated {
+            if self.evaluated - now > MAX_FALL_BACK {
+                self.evaluated = now;
+            }
+            return None;
+        }
+        let rule = due(rules, self.evaluated, now);
+        self.evaluated = now;
+        rule
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2024-01-01 was a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> i64 {
+        LocalTime {
+            year: 2024,
+            month: 1,
+            day,
+            hour,
+            minute,
+        }
+        .to_minutes()
+    }
+
+    fn rule(days: &str, time: &str) -> Rule {
+        parse_rule(days, time).unwrap()
+    }
+
+    #[test]
+    fn minutes_round_trip() {
+        for time in [
+            LocalTime {
+                year: 2024,
+                month: 2,
+                day: 29,
+                hour: 23,
+                minute: 59,
+            },
+            LocalTime {
+                year: 1999,
+                month: 12,
+                day: 31,
+                hour: 0,
+                minute: 0,
+            },
+        ] {
+            assert_eq!(LocalTime::from_minutes(time.to_minutes()), time);
+        }
+        assert_eq!(LocalTime::from_minutes(at(1, 8, 30)).weekday(), 0);
+        assert_eq!(LocalTime::from_minutes(at(7, 8, 30)).weekday(), 6);
+        assert_eq!(
+            LocalTime::from_minutes(at(3, 8, 5)).to_string(),
+            "Wed 08:05"
+        );
+    }
+
+    #[test]
+    fn parses_days() {
+        assert_eq!(
+            parse_days("mon-fri"),
+            Ok([true, true, true, true, true, false, false])
+        );
+        assert_eq!(parse_days("weekdays"), parse_days("mon-fri"));
+        assert_eq!(parse_days("Saturday, sun"), parse_days("weekends"));
+        assert_eq!(parse_days("daily"), Ok([true; 7]));
+        assert_eq!(
+            parse_days("wed"),
+            Ok([false, false, true, false, false, false, false])
+        );
+    }
+
+    #[test]
+    fn day_ranges_wrap_around_the_week() {
+        assert_eq!(
+            parse_days("fri-mon"),
+            Ok([true, false, false, false, true, true, true])
+        );
+        assert_eq!(
+            parse_days("sun-sun"),
+            Ok([false, false, false, false, false, false, true])
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_days() {
+        assert!(parse_days("funday").is_err());
+        assert!(parse_days("mon-xyz").is_err());
+        assert!(parse_days("").is_err());
+        assert!(parse_days("mon,,fri").is_err());
+    }
+
+    #[test]
+    fn parses_times() {
+        assert_eq!(parse_time("08:30"), Ok(510));
+        assert_eq!(parse_time(" 8:05 "), Ok(485));
+        assert_eq!(parse_time("00:00"), Ok(0));
+        assert_eq!(parse_time("23:59"), Ok(1439));
+    }
+
+    #[test]
+    fn rejects_invalid_times() {
+        for text in [
+            "24:00", "12:60", "8", "", "ab:cd", "-1:00", "12:30:00", "12h30",
+        ] {
+            assert!(parse_time(text).is_err(), "{} should be rejected", text);
+        }
+    }
+
+    #[test]
+    fn due_fires_in_the_window() {
+        let rules = [rule("weekdays", "18:00"), rule("weekdays", "08:30")];
+        assert_eq!(due(&rules, at(1, 17, 59), at(1, 18, 0)), Some(0));
+        assert_eq!(due(&rules, at(1, 18, 0), at(1, 18, 1)), None);
+        assert_eq!(due(&rules, at(2, 8, 29), at(2, 8, 30)), Some(1));
+        // Not on weekends
+        assert_eq!(due(&rules, at(6, 17, 59), at(6, 18, 0)), None);
+        assert_eq!(due(&rules, at(1, 18, 0), at(1, 18, 0)), None);
+    }
+
+    #[test]
+    fn only_the_latest_missed_trigger_fires() {
+        let rules = [rule("weekdays", "18:00"), rule("weekdays", "08:30")];
+        // Asleep from Monday noon to Tuesday 09:00: off at 18:00 and on at 08:30 were missed
+        assert_eq!(due(&rules, at(1, 12, 0), at(2, 9, 0)), Some(1));
+        // Asleep from Monday noon to Tuesday 19:00: the 18:00 off is the latest
+        assert_eq!(due(&rules, at(1, 12, 0), at(2, 19, 0)), Some(0));
+        // Asleep for two weeks
+        assert_eq!(due(&rules, at(1, 12, 0), at(15, 9, 0)), Some(1));
+        // Asleep over the weekend, from Friday night to Sunday
+        assert_eq!(due(&rules, at(5, 19, 0), at(7, 12, 0)), None);
+    }
+
+    #[test]
+    fn next_finds_the_closest_trigger() {
+        let rules = [rule("weekdays", "18:00"), rule("weekdays", "08:30")];
+        assert_eq!(
+            next(&rules, at(1, 12, 0)),
+            Some((0, LocalTime::from_minutes(at(1, 18, 0))))
+        );
+        assert_eq!(
+            next(&rules, at(1, 18, 0)),
+            Some((1, LocalTime::from_minutes(at(2, 8, 30))))
+        );
+    }
+
+    #[test]
+    fn next_crosses_the_week_boundary() {
+        let rules = [rule("weekdays", "18:00"), rule("weekdays", "08:30")];
+        // Friday evening to Monday morning
+        assert_eq!(
+            next(&rules, at(5, 18, 0)),
+            Some((1, LocalTime::from_minutes(at(8, 8, 30))))
+        );
+        // A weekly rule earlier in the day than now fires a week later
+        let weekly = [rule("mon", "08:00")];
+        assert_eq!(
+            next(&weekly, at(1, 9, 0)),
+            Some((0, LocalTime::from_minutes(at(8, 8, 0))))
+        );
+        assert_eq!(next(&[], at(1, 9, 0)), None);
+    }
+
+    #[test]
+    fn progress_fires_each_trigger_once() {
+        let rules = [rule("daily", "18:00")];
+        let mut progress = Progress::new(at(1, 17, 58));
+        assert_eq!(progress.advance(&rules, at(1, 17, 59)), None);
+        assert_eq!(progress.advance(&rules, at(1, 18, 0)), Some(0));
+        assert_eq!(progress.advance(&rules, at(1, 18, 0)), None);
+        assert_eq!(progress.advance(&rules, at(1, 18, 1)), None);
+    }
+
+    #[test]
+    fn fall_back_does_not_fire_the_repeated_hour_twice() {
+        // The clock goes from 03:00 back to 02:00, so 02:30 happens twice
+        let rules = [rule("daily", "02:30")];
+        let mut progress = Progress::new(at(1, 2, 0));
+        assert_eq!(progress.advance(&rules, at(1, 2, 30)), Some(0));
+        assert_eq!(progress.advance(&rules, at(1, 2, 59)), None);
+        assert_eq!(progress.advance(&rules, at(1, 2, 0)), None);
+        assert_eq!(progress.advance(&rules, at(1, 2, 30)), None);
+        assert_eq!(progress.advance(&rules, at(1, 2, 59)), None);
+        assert_eq!(progress.advance(&rules, at(1, 3, 0)), None);
+        // The next day fires again
+        assert_eq!(progress.advance(&rules, at(2, 2, 30)), Some(0));
+    }
+
+    #[test]
+    fn spring_forward_fires_the_skipped_hour() {
+        // The clock goes from 02:00 to 03:00, so 02:30 never shows on the wall clock
+        let rules = [rule("daily", "02:30")];
+        let mut progress = Progress::new(at(1, 1, 59));
+        assert_eq!(progress.advance(&rules, at(1, 3, 0)), Some(0));
+        assert_eq!(progress.advance(&rules, at(1, 3, 1)), None);
+    }
+
+    #[test]
+    fn large_backward_jump_resets_progress() {
+        let rules = [rule("daily", "12:00")];
+        let mut progress = Progress::new(at(2, 13, 0));
+        // The clock was set back a day
+        assert_eq!(progress.advance(&rules, at(1, 11, 0)), None);
+        assert_eq!(progress.advance(&rules, at(1, 12, 0)), Some(0));
+    }
+}
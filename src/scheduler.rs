@@ -0,0 +1,104 @@
+use std::sync::Mutex;
+
+use windows::Win32::System::SystemInformation::GetLocalTime;
+use windows::Win32::UI::WindowsAndMessaging::SetTimer;
+
+use crate::actions::{self, Action};
+use crate::config::Config;
+use crate::i18n::{t, tr};
+use crate::schedule::{self, LocalTime, Progress, Rule};
+use crate::tray::TRAY_HWND;
+
+pub const TIMER_ID: usize = 4;
+
+const DAY_KEYS: [&str; 7] = [
+    "day-mon", "day-tue", "day-wed", "day-thu", "day-fri", "day-sat", "day-sun",
+];
+
+// Rules are set to the minute, checking a few times a minute keeps them on time
+const TICK_MS: u32 = 20_000;
+
+struct Scheduler {
+    rules: Vec<Rule>,
+    actions: Vec<Action>,
+    progress: Progress,
+}
+
+static SCHEDULER: Mutex<Option<Scheduler>> = Mutex::new(None);
+
+fn local_now() -> i64 {
+    let time = unsafe { GetLocalTime() };
+    LocalTime {
+        year: time.wYear as i32,
+        month: time.wMonth as u32,
+        day: time.wDay as u32,
+        hour: time.wHour as u32,
+        minute: time.wMinute as u32,
+    }
+    .to_minutes()
+}
+
+/// Parses the configured schedules and starts checking them
+pub fn start(config: &Config) {
+    let mut rules = Vec::new();
+    let mut actions = Vec::new();
+    for entry in &config.schedules {
+        match schedule::parse_rule(&entry.days, &entry.at) {
+            Ok(rule) => {
+                rules.push(rule);
+                actions.push(entry.action.clone());
+            }
+            Err(e) => eprintln!("Invalid schedule \"{} {}\": {}", entry.days, entry.at, e),
+        }
+    }
+    if rules.is_empty() {
+        return;
+    }
+
+    eprintln!("{} schedule rule(s) active", rules.len());
+    *SCHEDULER.lock().unwrap() = Some(Scheduler {
+        rules,
+        actions,
+        progress: Progress::new(local_now()),
+    });
+    let hwnd = unsafe { TRAY_HWND };
+    if !hwnd.is_invalid() {
+        unsafe { SetTimer(Some(hwnd), TIMER_ID, TICK_MS, None) };
+    }
+}
+
+/// Runs the rule that came due since the last check, including ones missed during sleep
+pub fn tick(config: &mut Config) {
+    let action = {
+        let mut scheduler = SCHEDULER.lock().unwrap();
+        let Some(scheduler) = scheduler.as_mut() else {
+            return;
+        };
+        let Some(index) = scheduler.progress.advance(&scheduler.rules, local_now()) else {
+            return;
+        };
+        scheduler.actions[index].clone()
+    };
+    eprintln!("Schedule: {}", action.label());
+    actions::run(&action, config);
+}
+
+/// Describes the next scheduled event, e.g. "Next: Turn off, Mon 18:00"
+pub fn next_event() -> Option<String> {
+    let scheduler = SCHEDULER.lock().unwrap();
+    let scheduler = scheduler.as_ref()?;
+    let (index, at) = schedule::next(&scheduler.rules, local_now())?;
+    let time = format!(
+        "{} {:02}:{:02}",
+        t(DAY_KEYS[at.weekday()]),
+        at.hour,
+        at.minute
+    );
+    Some(tr(
+        "schedule-next",
+        &[
+            ("action", &scheduler.actions[index].label()),
+            ("time", &time),
+        ],
+    ))
+}
@@ -0,0 +1,214 @@
+use crate::hotkeys::Chord;
+
+/// Result of feeding one chord into the matcher
+#[derive(Debug, PartialEq)]
+pub enum Step<A> {
+    /// A binding matched and nothing longer can follow
+    Matched(A),
+    /// The chords so far are the prefix of a longer binding
+    Pending,
+    /// The chords don't lead anywhere, the sequence was dropped
+    NoMatch,
+}
+
+/// Matches leader-key sequences like `Ctrl+Alt+T, 2` against the pressed chords.
+/// Timing is left to the caller, which calls `timeout` once the sequence window runs out.
+pub struct SequenceMatcher<A> {
+    bindings: Vec<(Vec<Chord>, A)>,
+    pending: Vec<Chord>,
+}
+
+impl<A: Clone> SequenceMatcher<A> {
+    pub fn new(bindings: Vec<(Vec<Chord>, A)>) -> Self {
+        SequenceMatcher {
+            bindings,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// First chord of every binding, these are the ones registered globally
+    pub fn first_chords(&self) -> Vec<Chord> {
+        let mut chords: Vec<Chord> = Vec::new();
+        for (sequence, _) in &self.bindings {
+            if let Some(first) = sequence.first() {
+                if !chords.contains(first) {
+                    chords.push(*first);
+                }
+            }
+        }
+        chords
+    }
+
+    /// Chords that can continue the pending sequence
+    pub fn next_chords(&self) -> Vec<Chord> {
+        let mut chords: Vec<Chord> = Vec::new();
+        for (sequence, _) in &self.bindings {
+            if sequence.len() > self.pending.len() && sequence.starts_with(&self.pending) {
+                let next = sequence[self.pending.len()];
+                if !chords.contains(&next) {
+                    chords.push(next);
+                }
+            }
+        }
+        chords
+    }
+
+    pub fn feed(&mut self, chord: Chord) -> Step<A> {
+        let mut sequence = self.pending.clone();
+        sequence.push(chord);
+        let exact = self
+            .bindings
+            .iter()
+            .find(|(s, _)| *s == sequence)
+            .map(|(_, action)| action.clone());
+        let longer = self
+            .bindings
+            .iter()
+            .any(|(s, _)| s.len() > sequence.len() && s.starts_with(&sequence));
+
+        if longer {
+            self.pending = sequence;
+            return Step::Pending;
+        }
+        if let Some(action) = exact {
+            self.pending.clear();
+            return Step::Matched(action);
+        }
+        if self.is_pending() {
+            // A dead end may still be the start of a new sequence
+            self.pending.clear();
+            return self.feed(chord);
+        }
+        Step::NoMatch
+    }
+
+    /// Ends the pending sequence, returning its action if it is a complete binding
+    pub fn timeout(&mut self) -> Option<A> {
+        let sequence = std::mem::take(&mut self.pending);
+        self.bindings
+            .iter()
+            .find(|(s, _)| *s == sequence)
+            .map(|(_, action)| action.clone())
+    }
+
+    pub fn reset(&mut self) {
+        self.pending.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hotkeys::parse_chord;
+
+    fn chords(text: &str) -> Vec<Chord> {
+        text.split(',').map(|c| parse_chord(c).unwrap()).collect()
+    }
+
+    fn chord(text: &str) -> Chord {
+        parse_chord(text).unwrap()
+    }
+
+    fn matcher() -> SequenceMatcher<&'static str> {
+        SequenceMatcher::new(vec![
+            (chords("Ctrl+Alt+T, 1"), "monitor 1"),
+            (chords("Ctrl+Alt+T, 2"), "monitor 2"),
+            (chords("Ctrl+Alt+T, Shift+2"), "monitor 2 off"),
+            (chords("Ctrl+K"), "toggle"),
+            (chords("Ctrl+K, O"), "off"),
+            (chords("Ctrl+F12"), "restore"),
+        ])
+    }
+
+    #[test]
+    fn single_chord_matches() {
+        let mut matcher = matcher();
+        assert_eq!(matcher.feed(chord("Ctrl+F12")), Step::Matched("restore"));
+        assert!(!matcher.is_pending());
+    }
+
+    #[test]
+    fn sequence_is_pending_then_matches() {
+        let mut matcher = matcher();
+        assert_eq!(matcher.feed(chord("Ctrl+Alt+T")), Step::Pending);
+        assert!(matcher.is_pending());
+        assert_eq!(matcher.feed(chord("2")), Step::Matched("monitor 2"));
+        assert!(!matcher.is_pending());
+    }
+
+    #[test]
+    fn unknown_chord_does_not_match() {
+        let mut matcher = matcher();
+        assert_eq!(matcher.feed(chord("Ctrl+Q")), Step::NoMatch);
+        assert_eq!(matcher.feed(chord("Ctrl+Alt+T")), Step::Pending);
+        assert_eq!(matcher.feed(chord("3")), Step::NoMatch);
+        assert!(!matcher.is_pending());
+    }
+
+    #[test]
+    fn dead_end_restarts_as_a_new_sequence() {
+        let mut matcher = matcher();
+        assert_eq!(matcher.feed(chord("Ctrl+Alt+T")), Step::Pending);
+        // Not a continuation, but a binding of its own
+        assert_eq!(matcher.feed(chord("Ctrl+F12")), Step::Matched("restore"));
+        assert_eq!(matcher.feed(chord("Ctrl+Alt+T")), Step::Pending);
+        // The start of another sequence
+        assert_eq!(matcher.feed(chord("Ctrl+K")), Step::Pending);
+        assert_eq!(matcher.feed(chord("O")), Step::Matched("off"));
+    }
+
+    #[test]
+    fn timeout_runs_a_complete_shorter_binding() {
+        let mut matcher = matcher();
+        assert_eq!(matcher.feed(chord("Ctrl+K")), Step::Pending);
+        assert_eq!(matcher.timeout(), Some("toggle"));
+        assert!(!matcher.is_pending());
+
+        assert_eq!(matcher.feed(chord("Ctrl+K")), Step::Pending);
+        assert_eq!(matcher.feed(chord("O")), Step::Matched("off"));
+        assert_eq!(matcher.timeout(), None);
+    }
+
+    #[test]
+    fn timeout_of_an_incomplete_sequence_runs_nothing() {
+        let mut matcher = matcher();
+        assert_eq!(matcher.feed(chord("Ctrl+Alt+T")), Step::Pending);
+        assert_eq!(matcher.timeout(), None);
+        assert!(!matcher.is_pending());
+        assert_eq!(matcher.timeout(), None);
+    }
+
+    #[test]
+    fn reset_drops_the_pending_sequence() {
+        let mut matcher = matcher();
+        assert_eq!(matcher.feed(chord("Ctrl+Alt+T")), Step::Pending);
+        matcher.reset();
+        assert_eq!(matcher.feed(chord("2")), Step::NoMatch);
+    }
+
+    #[test]
+    fn first_chords_are_unique() {
+        assert_eq!(
+            matcher().first_chords(),
+            vec![chord("Ctrl+Alt+T"), chord("Ctrl+K"), chord("Ctrl+F12")]
+        );
+    }
+
+    #[test]
+    fn next_chords_follow_the_pending_sequence() {
+        let mut matcher = matcher();
+        assert_eq!(matcher.next_chords(), matcher.first_chords());
+        assert_eq!(matcher.feed(chord("Ctrl+Alt+T")), Step::Pending);
+        assert_eq!(
+            matcher.next_chords(),
+            vec![chord("1"), chord("2"), chord("Shift+2")]
+        );
+        matcher.reset();
+        assert_eq!(matcher.feed(chord("Ctrl+K")), Step::Pending);
+        assert_eq!(matcher.next_chords(), vec![chord("O")]);
+    }
+}
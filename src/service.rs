@@ -0,0 +1,56 @@
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::WindowsAndMessaging::{
+    DispatchMessageW, GetMessageW, TranslateMessage, MSG,
+};
+
+use crate::app_rules;
+use crate::config::Config;
+use crate::hotkeys;
+use crate::idle;
+use crate::overlay;
+use crate::power;
+use crate::recovery::{self, ExitReason};
+use crate::rename;
+use crate::scheduler;
+use crate::session;
+use crate::timer;
+
+/// Starts hotkeys, timers and policies. They run with or without the tray icon,
+/// driven by messages to the hidden window.
+pub fn start(hwnd: HWND, config: &mut Config) {
+    hotkeys::register_all(hwnd, config);
+    recovery::register_hotkey(hwnd, config);
+    recovery::install_ctrl_handler();
+    session::register(hwnd, config);
+    overlay::restore(config);
+
+    // Restore monitors left off by a timer that ran out while the app was closed
+    timer::resume(config);
+    idle::start(config);
+    scheduler::start(config);
+    power::start(config);
+    app_rules::start(config);
+}
+
+/// Runs the message loop until the app quits
+pub fn run() {
+    let mut msg = MSG::default();
+    while unsafe { GetMessageW(&mut msg, None, 0, 0) }.as_bool() {
+        if rename::is_dialog_message(&msg) {
+            continue;
+        }
+        let _ = unsafe { TranslateMessage(&msg) };
+        unsafe {
+            DispatchMessageW(&msg);
+        }
+    }
+}
+
+pub fn stop(hwnd: HWND, config: &mut Config) {
+    hotkeys::unregister_all(hwnd);
+    recovery::unregister_hotkey(hwnd);
+    app_rules::stop();
+    session::unregister(hwnd, config);
+    recovery::on_exit(config, ExitReason::Quit);
+    overlay::release();
+}
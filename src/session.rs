@@ -0,0 +1,69 @@
+use std::sync::Mutex;
+
+use windows::Win32::Foundation::HWND;
+use windows::Win32::System::RemoteDesktop::{
+    WTSRegisterSessionNotification, WTSUnRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION,
+};
+use windows::Win32::UI::WindowsAndMessaging::{WTS_SESSION_LOCK, WTS_SESSION_UNLOCK};
+
+use crate::config::Config;
+use crate::monitors::{then, track, turn_off, turn_on};
+use crate::topology;
+
+/// Monitors turned off when the session locked, restored on unlock
+static LOCKED_OFF: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn register(hwnd: HWND, config: &Config) {
+    if !config.lock.off_on_lock {
+        return;
+    }
+    if let Err(e) = unsafe { WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION) } {
+        eprintln!("Failed to register for session notifications: {}", e);
+    }
+}
+
+pub fn unregister(hwnd: HWND, config: &Config) {
+    if config.lock.off_on_lock {
+        let _ = unsafe { WTSUnRegisterSessionNotification(hwnd) };
+    }
+}
+
+/// Handles `WM_WTSSESSION_CHANGE`
+pub fn handle_session_change(config: &mut Config, event: u32) {
+    if !config.lock.off_on_lock {
+        return;
+    }
+    match event {
+        WTS_SESSION_LOCK => {
+            // Every non-primary monitor, not only the selected ones
+            let primary = topology::primary();
+            let monitors: Vec<String> = topology::monitors()
+                .into_iter()
+                .map(|(m, _)| m)
+                .filter(|m| Some(m) != primary.as_ref())
+                .collect();
+            eprintln!("Session locked, turning off {:?}", monitors);
+            let requested = monitors.clone();
+            track(
+                config,
+                |config| turn_off(config, &requested),
+                // Only restore what this policy turned off
+                move |config, already_off| {
+                    *LOCKED_OFF.lock().unwrap() = monitors
+                        .into_iter()
+                        .filter(|m| config.saved_modes.contains_key(m) && !already_off.contains(m))
+                        .collect();
+                },
+            );
+        }
+        // Taken once a lock still waiting for a hook has been recorded
+        WTS_SESSION_UNLOCK => then(config, |config| {
+            let monitors = std::mem::take(&mut *LOCKED_OFF.lock().unwrap());
+            if config.lock.restore_on_unlock && !monitors.is_empty() {
+                eprintln!("Session unlocked, turning monitors back on");
+                turn_on(config, &monitors);
+            }
+        }),
+        _ => {}
+    }
+}
@@ -0,0 +1,161 @@
+use std::sync::Mutex;
+
+use windows::core::w;
+use windows::Win32::Foundation::{COLORREF, ERROR_SUCCESS, POINT};
+use windows::Win32::Graphics::Gdi::{
+    GetSysColor, MonitorFromPoint, COLOR_GRAYTEXT, COLOR_HIGHLIGHT, COLOR_HIGHLIGHTTEXT,
+    COLOR_MENU, COLOR_MENUTEXT, MONITOR_DEFAULTTONEAREST, SYS_COLOR_INDEX,
+};
+use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD};
+use windows::Win32::UI::Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW};
+use windows::Win32::UI::HiDpi::{GetDpiForMonitor, MDT_EFFECTIVE_DPI};
+use windows::Win32::UI::WindowsAndMessaging::{
+    SystemParametersInfoW, SPI_GETHIGHCONTRAST, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+};
+
+use crate::config::{ThemeConfig, ThemeMode};
+
+/// Colors and scale for the owner-drawn tray menu
+#[derive(Clone, Copy)]
+pub struct Theme {
+    pub background: COLORREF,
+    pub highlight: COLORREF,
+    pub text: COLORREF,
+    pub highlight_text: COLORREF,
+    pub disabled_text: COLORREF,
+    pub separator: COLORREF,
+    pub dpi: u32,
+}
+
+const DARK: Theme = Theme {
+    background: COLORREF(0x00222222),
+    highlight: COLORREF(0x00333333),
+    text: COLORREF(0x00FFFFFF),
+    highlight_text: COLORREF(0x00FFFFFF),
+    disabled_text: COLORREF(0x00888888),
+    separator: COLORREF(0x00444444),
+    dpi: 96,
+};
+
+const LIGHT: Theme = Theme {
+    background: COLORREF(0x00F9F9F9),
+    highlight: COLORREF(0x00E5E5E5),
+    text: COLORREF(0x00000000),
+    highlight_text: COLORREF(0x00000000),
+    disabled_text: COLORREF(0x006D6D6D),
+    separator: COLORREF(0x00D7D7D7),
+    dpi: 96,
+};
+
+static CURRENT: Mutex<Theme> = Mutex::new(DARK);
+
+impl Theme {
+    /// Scales a size given for 96 DPI
+    pub fn scale(&self, size: i32) -> i32 {
+        size * self.dpi as i32 / 96
+    }
+}
+
+fn high_contrast() -> bool {
+    let mut info = HIGHCONTRASTW {
+        cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+        ..Default::default()
+    };
+    unsafe {
+        SystemParametersInfoW(
+            SPI_GETHIGHCONTRAST,
+            info.cbSize,
+            Some(&mut info as *mut _ as *mut std::ffi::c_void),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    }
+    .is_ok()
+        && (info.dwFlags.0 & HCF_HIGHCONTRASTON.0) != 0
+}
+
+/// Whether apps should use the light theme, per the Windows personalization setting
+fn system_uses_light_theme() -> bool {
+    let mut value = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            w!("Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize"),
+            w!("AppsUseLightTheme"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut _ as *mut std::ffi::c_void),
+            Some(&mut size),
+        )
+    };
+    result == ERROR_SUCCESS && value != 0
+}
+
+fn system_color(index: SYS_COLOR_INDEX) -> COLORREF {
+    COLORREF(unsafe { GetSysColor(index) })
+}
+
+/// Parses `#RRGGBB`
+fn parse_color(text: &str) -> Option<COLORREF> {
+    let hex = text.trim().strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let rgb = u32::from_str_radix(hex, 16).ok()?;
+    let (r, g, b) = (rgb >> 16, (rgb >> 8) & 0xFF, rgb & 0xFF);
+    Some(COLORREF(b << 16 | g << 8 | r))
+}
+
+fn apply_override(color: &mut COLORREF, value: &Option<String>) {
+    if let Some(text) = value {
+        match parse_color(text) {
+            Some(parsed) => *color = parsed,
+            None => eprintln!("Invalid theme color \"{}\", expected #RRGGBB", text),
+        }
+    }
+}
+
+fn dpi_at(point: POINT) -> u32 {
+    let monitor = unsafe { MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST) };
+    let (mut x, mut y) = (96, 96);
+    match unsafe { GetDpiForMonitor(monitor, MDT_EFFECTIVE_DPI, &mut x, &mut y) } {
+        Ok(()) => x,
+        Err(_) => 96,
+    }
+}
+
+/// Picks the theme for a menu about to open at `point`.
+/// High contrast always uses the system colors, ignoring overrides.
+pub fn refresh(config: &ThemeConfig, point: POINT) {
+    let mut theme = if high_contrast() {
+        Theme {
+            background: system_color(COLOR_MENU),
+            highlight: system_color(COLOR_HIGHLIGHT),
+            text: system_color(COLOR_MENUTEXT),
+            highlight_text: system_color(COLOR_HIGHLIGHTTEXT),
+            disabled_text: system_color(COLOR_GRAYTEXT),
+            separator: system_color(COLOR_GRAYTEXT),
+            dpi: 96,
+        }
+    } else {
+        let mut theme = match config.mode {
+            ThemeMode::Dark => DARK,
+            ThemeMode::Light => LIGHT,
+            ThemeMode::System if system_uses_light_theme() => LIGHT,
+            ThemeMode::System => DARK,
+        };
+        apply_override(&mut theme.background, &config.background);
+        apply_override(&mut theme.highlight, &config.highlight);
+        apply_override(&mut theme.text, &config.text);
+        apply_override(&mut theme.highlight_text, &config.highlight_text);
+        apply_override(&mut theme.disabled_text, &config.disabled_text);
+        apply_override(&mut theme.separator, &config.separator);
+        theme
+    };
+    theme.dpi = dpi_at(point);
+    *CURRENT.lock().unwrap() = theme;
+}
+
+pub fn current() -> Theme {
+    *CURRENT.lock().unwrap()
+}
@@ -0,0 +1,114 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use windows::Win32::UI::WindowsAndMessaging::{KillTimer, SetTimer};
+
+use crate::config::{save_config, Config};
+use crate::monitors::{is_busy, track, turn_off, turn_on};
+use crate::tray::{update_tooltip, TRAY_HWND};
+
+pub const TIMER_ID: usize = 2;
+
+/// Durations offered in the tray menu, in minutes
+pub const PRESETS: [u32; 3] = [25, 50, 90];
+
+// The tooltip shows whole minutes, so checking twice a minute is enough
+const TICK_MS: u32 = 30_000;
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Minutes left until the monitors come back on, rounded up
+pub fn remaining_minutes(config: &Config) -> Option<u64> {
+    let until = config.timer_until?;
+    Some(until.saturating_sub(now()).div_ceil(60))
+}
+
+/// Turns the secondary monitors that are still on off and schedules them to come back on.
+/// Monitors that were already off are left to whatever turned them off.
+pub fn start(config: &mut Config, minutes: u32) {
+    let monitors = config.secondary_monitors.clone();
+    let requested = monitors.clone();
+    track(
+        config,
+        |config| turn_off(config, &requested),
+        move |config, already_off| {
+            // A running timer keeps the monitors it turned off, and restores these along with them
+            config.timer_monitors.extend(
+                monitors
+                    .into_iter()
+                    .filter(|m| config.saved_modes.contains_key(m) && !already_off.contains(m)),
+            );
+            forget_restored(config);
+            if config.timer_monitors.is_empty() {
+                // Nothing was turned off, e.g. a hook vetoed it or the monitors were off already
+                return;
+            }
+            eprintln!("Monitors will come back on in {} minutes", minutes);
+            config.timer_until = Some(now() + minutes as u64 * 60);
+            save_config(config);
+            arm();
+            update_tooltip(config);
+        },
+    );
+}
+
+fn arm() {
+    let hwnd = unsafe { TRAY_HWND };
+    if !hwnd.is_invalid() {
+        unsafe { SetTimer(Some(hwnd), TIMER_ID, TICK_MS, None) };
+    }
+}
+
+pub fn stop() {
+    let hwnd = unsafe { TRAY_HWND };
+    if !hwnd.is_invalid() {
+        let _ = unsafe { KillTimer(Some(hwnd), TIMER_ID) };
+    }
+}
+
+/// Forgets timed-off monitors that are back on, and the timer once none are left
+pub fn forget_restored(config: &mut Config) {
+    let saved_modes = &config.saved_modes;
+    config
+        .timer_monitors
+        .retain(|m| saved_modes.contains_key(m));
+    if config.timer_monitors.is_empty() && config.timer_until.take().is_some() {
+        stop();
+    }
+}
+
+/// Picks up a timer saved by a previous run
+pub fn resume(config: &mut Config) {
+    if config.timer_until.is_none() {
+        return;
+    }
+    forget_restored(config);
+    if config.timer_until.is_none() {
+        save_config(config);
+    } else {
+        arm();
+        tick(config);
+    }
+}
+
+/// Handles the timer, restoring the monitors it turned off once it has run out.
+/// The timer stays until they are back on, so a restore that failed is retried.
+pub fn tick(config: &mut Config) {
+    match config.timer_until {
+        // A restore still waiting for a hook is not requested again
+        Some(until) if until <= now() && !is_busy() => {
+            eprintln!("Timer ran out, turning monitors back on");
+            let monitors = config.timer_monitors.clone();
+            turn_on(config, &monitors);
+            forget_restored(config);
+            save_config(config);
+            update_tooltip(config);
+        }
+        Some(_) => update_tooltip(config),
+        None => stop(),
+    }
+}
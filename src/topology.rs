@@ -0,0 +1,122 @@
+use std::sync::{Arc, Mutex};
+
+use crate::monitors::{
+    current_details, get_connectors, get_monitors, get_primary_monitor, MonitorDetails,
+};
+
+/// Active monitors as last enumerated
+struct Topology {
+    monitors: Vec<(String, String)>, // device name, friendly name
+    primary: Option<String>,
+    details: Arc<[(String, MonitorDetails)]>, // device name, details
+}
+
+// Enumerating runs several display APIs per monitor, so menus and policies read
+// this instead. Cleared on WM_DISPLAYCHANGE and after every change made here.
+static CACHE: Mutex<Option<Topology>> = Mutex::new(None);
+
+fn enumerate() -> Topology {
+    let monitors = get_monitors();
+    let connectors = get_connectors();
+    let details = monitors
+        .iter()
+        .filter_map(|(m, _)| {
+            let details = MonitorDetails {
+                connector: connectors.get(m).cloned(),
+                ..current_details(m)?
+            };
+            Some((m.clone(), details))
+        })
+        .collect();
+    Topology {
+        monitors,
+        primary: get_primary_monitor(),
+        details,
+    }
+}
+
+fn read_cached<T>(
+    cache: &Mutex<Option<Topology>>,
+    load: fn() -> Topology,
+    read: impl FnOnce(&Topology) -> T,
+) -> T {
+    let mut cache = cache.lock().unwrap();
+    read(cache.get_or_insert_with(load))
+}
+
+fn with_topology<T>(read: impl FnOnce(&Topology) -> T) -> T {
+    read_cached(&CACHE, enumerate, read)
+}
+
+/// Cached `get_monitors`
+pub fn monitors() -> Vec<(String, String)> {
+    with_topology(|t| t.monitors.clone())
+}
+
+/// Cached `get_primary_monitor`
+pub fn primary() -> Option<String> {
+    with_topology(|t| t.primary.clone())
+}
+
+/// Mode, position and connector of each active monitor, shared with the cache
+pub fn details() -> Arc<[(String, MonitorDetails)]> {
+    with_topology(|t| Arc::clone(&t.details))
+}
+
+pub fn invalidate() {
+    *CACHE.lock().unwrap() = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    static ENUMERATIONS: AtomicU32 = AtomicU32::new(0);
+
+    /// Stands in for the display APIs, which take about a millisecond per call
+    fn fake_enumerate() -> Topology {
+        ENUMERATIONS.fetch_add(1, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(1));
+        Topology {
+            monitors: vec![
+                ("\\\\.\\DISPLAY1".to_string(), "Laptop".to_string()),
+                ("\\\\.\\DISPLAY2".to_string(), "Dell".to_string()),
+            ],
+            primary: Some("\\\\.\\DISPLAY1".to_string()),
+            details: Arc::new([]),
+        }
+    }
+
+    fn time(runs: u32, f: impl Fn()) -> Duration {
+        let start = Instant::now();
+        for _ in 0..runs {
+            f();
+        }
+        start.elapsed() / runs
+    }
+
+    #[test]
+    fn cached_enumeration_is_cheaper() {
+        const RUNS: u32 = 50;
+        let uncached = time(RUNS, || {
+            std::hint::black_box(fake_enumerate());
+        });
+        ENUMERATIONS.store(0, Ordering::SeqCst);
+
+        let cache = Mutex::new(None);
+        let cached = time(RUNS, || {
+            std::hint::black_box(read_cached(&cache, fake_enumerate, |t| {
+                (
+                    t.monitors.clone(),
+                    t.primary.clone(),
+                    Arc::clone(&t.details),
+                )
+            }));
+        });
+        assert_eq!(ENUMERATIONS.load(Ordering::SeqCst), 1);
+        assert!(cached < uncached, "{:?} vs {:?}", cached, uncached);
+    }
+}
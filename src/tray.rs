@@ -1,24 +1,61 @@
-use windows::core::PCWSTR;
-use windows::Win32::Foundation::{COLORREF, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use windows::core::{PCWSTR, PWSTR};
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, POINT, RECT, WPARAM};
 use windows::Win32::Graphics::Gdi::{
     CreatePen, CreateSolidBrush, DeleteObject, DrawTextW, FillRect, LineTo, MoveToEx, SelectObject,
-    SetBkColor, SetTextColor, DT_LEFT, DT_SINGLELINE, DT_VCENTER, HGDIOBJ, PS_SOLID,
+    SetBkColor, SetTextColor, DT_HIDEPREFIX, DT_LEFT, DT_SINGLELINE, DT_VCENTER, HGDIOBJ, PS_SOLID,
 };
+use windows::Win32::System::DataExchange::COPYDATASTRUCT;
 use windows::Win32::System::Registry::{
     RegCloseKey, RegDeleteValueW, RegOpenKeyExW, RegQueryValueExW, RegSetValueExW, HKEY,
     HKEY_CURRENT_USER, KEY_READ, KEY_WRITE, REG_SZ,
 };
-use windows::Win32::UI::Controls::{DRAWITEMSTRUCT, MEASUREITEMSTRUCT, ODS_SELECTED, ODT_MENU};
-use windows::Win32::UI::Shell::{Shell_NotifyIconW, NIF_ICON, NIM_MODIFY, NOTIFYICONDATAW};
+use windows::Win32::UI::Accessibility::{MSAAMENUINFO, MSAA_MENU_SIG};
+use windows::Win32::UI::Controls::{
+    DRAWITEMSTRUCT, MEASUREITEMSTRUCT, ODS_NOACCEL, ODS_SELECTED, ODT_MENU,
+};
+use windows::Win32::UI::Input::KeyboardAndMouse::{GetDoubleClickTime, GetKeyState, VK_SHIFT};
+use windows::Win32::UI::Shell::{
+    Shell_NotifyIconW, NIF_ICON, NIF_INFO, NIF_MESSAGE, NIF_TIP, NIM_ADD, NIM_DELETE, NIM_MODIFY,
+    NIM_SETVERSION, NIN_BALLOONUSERCLICK, NIN_SELECT, NOTIFYICONDATAW, NOTIFYICON_VERSION,
+    NOTIFY_ICON_INFOTIP_FLAGS,
+};
 use windows::Win32::UI::WindowsAndMessaging::{
-    AppendMenuW, CreatePopupMenu, DefWindowProcW, DestroyMenu, GetCursorPos, LoadImageW,
-    PostMessageW, PostQuitMessage, SetForegroundWindow, TrackPopupMenu, HICON, IMAGE_FLAGS,
-    IMAGE_ICON, MF_OWNERDRAW, TPM_NONOTIFY, TPM_RETURNCMD, WM_DESTROY, WM_HOTKEY, WM_LBUTTONDBLCLK,
-    WM_NULL, WM_RBUTTONUP, WM_USER,
+    AppendMenuW, CreatePopupMenu, DefWindowProcW, DestroyIcon, DestroyMenu, GetCursorPos,
+    KillTimer, LoadImageW, PostMessageW, PostQuitMessage, SetForegroundWindow, SetTimer,
+    TrackPopupMenu, HICON, HMENU, IMAGE_FLAGS, IMAGE_ICON, MF_CHECKED, MF_DISABLED, MF_GRAYED,
+    MF_OWNERDRAW, MF_POPUP, MNC_EXECUTE, PBT_APMPOWERSTATUSCHANGE, TPM_NONOTIFY, TPM_RETURNCMD,
+    WM_CONTEXTMENU, WM_COPYDATA, WM_DESTROY, WM_DISPLAYCHANGE, WM_ENDSESSION, WM_HOTKEY,
+    WM_LBUTTONDBLCLK, WM_LBUTTONUP, WM_MBUTTONUP, WM_MENUCHAR, WM_NULL, WM_POWERBROADCAST,
+    WM_TIMER, WM_USER, WM_WTSSESSION_CHANGE,
 };
 
-use crate::config::{save_config, Config};
-use crate::monitors::{get_monitors, toggle_monitors};
+use crate::actions::{self, Action};
+use crate::app_rules;
+use crate::cli::COPYDATA_ACTION;
+use crate::config::{save_config, ClickAction, Config, TrayAction, CONFIG_ERROR};
+use crate::confirm;
+use crate::hooks;
+use crate::hotkeys::{self, FAILED_HOTKEYS};
+use crate::i18n::{t, tr};
+use crate::icon;
+use crate::identify;
+use crate::idle;
+use crate::menu::{mnemonic, Item, ItemKind, Menu, MenuCommand, MenuState};
+use crate::monitors::{
+    self, monitor_label, monitor_name, restore_all, toggle_monitors, turn_off, turn_on,
+};
+use crate::notify;
+use crate::power;
+use crate::recovery;
+use crate::rename;
+use crate::scheduler;
+use crate::session;
+use crate::theme;
+use crate::timer;
+use crate::topology;
 
 pub static mut CONFIG: *mut Config = std::ptr::null_mut();
 
@@ -27,6 +64,8 @@ pub static mut TRAY_HWND: HWND = HWND(std::ptr::null_mut());
 pub static mut HINSTANCE: windows::Win32::Foundation::HINSTANCE =
     windows::Win32::Foundation::HINSTANCE(std::ptr::null_mut());
 
+pub const WINDOW_CLASS: &str = "ScreenOffTrayClass";
+
 pub const IDI_SCREEN_ON: u16 = 101;
 pub const IDI_SCREEN_OFF: u16 = 102;
 
@@ -47,21 +86,228 @@ pub fn load_icon_from_resource(id: u16) -> HICON {
     }
 }
 
-pub fn update_tray_icon(icon_id: u16) {
-    let hicon = load_icon_from_resource(icon_id);
+/// Set while the notification area icon exists, never in headless mode
+static ICON_ADDED: AtomicBool = AtomicBool::new(false);
+
+// The rendered icon shown now, destroyed when replaced
+static RENDERED_ICON: Mutex<Option<isize>> = Mutex::new(None);
+
+/// The icon for the current state: the rendered layout, or the static icon when that fails
+fn state_icon(config: &Config) -> HICON {
+    let rendered = icon::layout_icon(config);
+    let previous = std::mem::replace(
+        &mut *RENDERED_ICON.lock().unwrap(),
+        rendered.map(|icon| icon.0 as isize),
+    );
+    if let Some(previous) = previous {
+        // Replacing the tray icon copies it, so the old one can go
+        let _ = unsafe { DestroyIcon(HICON(previous as *mut _)) };
+    }
+    rendered.unwrap_or_else(|| {
+        load_icon_from_resource(if config.saved_modes.is_empty() {
+            IDI_SCREEN_ON
+        } else {
+            IDI_SCREEN_OFF
+        })
+    })
+}
+
+pub fn add_icon(hwnd: HWND, config: &Config) {
+    let mut nid: NOTIFYICONDATAW = unsafe { std::mem::zeroed() };
+    nid.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+    nid.hWnd = hwnd;
+    nid.uID = 1;
+    nid.uFlags = NIF_ICON | NIF_MESSAGE | NIF_TIP;
+    nid.uCallbackMessage = WM_USER;
+    nid.hIcon = state_icon(config);
+    set_tooltip(&mut nid, &tooltip_text(config));
+    unsafe { Shell_NotifyIconW(NIM_ADD, &nid).unwrap() };
+    // Adds keyboard selection of the icon, reported as NIN_KEYSELECT and WM_CONTEXTMENU
+    nid.Anonymous.uVersion = NOTIFYICON_VERSION;
+    let _ = unsafe { Shell_NotifyIconW(NIM_SETVERSION, &nid) };
+    ICON_ADDED.store(true, Ordering::SeqCst);
+}
+
+pub fn remove_icon(hwnd: HWND) {
+    if !ICON_ADDED.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let mut nid: NOTIFYICONDATAW = unsafe { std::mem::zeroed() };
+    nid.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+    nid.hWnd = hwnd;
+    nid.uID = 1;
+    let _ = unsafe { Shell_NotifyIconW(NIM_DELETE, &nid) };
+}
+
+pub fn update_tray_icon(config: &Config) {
+    // No icon when running headless or a command line action without a tray instance
+    let hwnd = unsafe { TRAY_HWND };
+    if hwnd.is_invalid() || !ICON_ADDED.load(Ordering::SeqCst) {
+        return;
+    }
+    let hicon = state_icon(config);
     let mut nid: NOTIFYICONDATAW = unsafe { std::mem::zeroed() };
     nid.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
-    nid.hWnd = unsafe { TRAY_HWND };
+    nid.hWnd = hwnd;
     nid.uID = 1;
     nid.uFlags = NIF_ICON;
     nid.hIcon = hicon;
-    unsafe { Shell_NotifyIconW(NIM_MODIFY, &nid).unwrap() };
+    // Fails while Explorer restarts, a panic here would leave the monitors off
+    if let Err(e) = unsafe { Shell_NotifyIconW(NIM_MODIFY, &nid) }.ok() {
+        eprintln!("Failed to update the tray icon: {}", e);
+    }
+}
+
+/// Tray tooltip, with the monitors that are off, the time left on a timer and any hotkeys
+/// that failed to register
+pub fn tooltip_text(config: &Config) -> String {
+    let mut tip = t("app-name");
+    if !config.saved_modes.is_empty() {
+        let mut off: Vec<String> = config
+            .saved_modes
+            .keys()
+            .map(|m| monitor_label(config, m))
+            .collect();
+        off.sort();
+        tip.push('\n');
+        tip.push_str(&tr("tooltip-off", &[("monitors", &off.join(", "))]));
+    }
+    if let Some(minutes) = timer::remaining_minutes(config) {
+        tip.push('\n');
+        tip.push_str(&tr("tooltip-back-on", &[("minutes", &minutes)]));
+    }
+    for failure in FAILED_HOTKEYS.lock().unwrap().iter() {
+        tip.push('\n');
+        tip.push_str(failure);
+    }
+    tip
+}
+
+pub fn update_tooltip(config: &Config) {
+    let hwnd = unsafe { TRAY_HWND };
+    if hwnd.is_invalid() || !ICON_ADDED.load(Ordering::SeqCst) {
+        return;
+    }
+    let mut nid: NOTIFYICONDATAW = unsafe { std::mem::zeroed() };
+    nid.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+    nid.hWnd = hwnd;
+    nid.uID = 1;
+    nid.uFlags = NIF_TIP;
+    set_tooltip(&mut nid, &tooltip_text(config));
+    let _ = unsafe { Shell_NotifyIconW(NIM_MODIFY, &nid) };
+}
+
+pub fn set_tooltip(nid: &mut NOTIFYICONDATAW, text: &str) {
+    // szTip holds at most 127 characters plus the terminator
+    let tip_wide: Vec<u16> = text.encode_utf16().take(nid.szTip.len() - 1).collect();
+    nid.szTip[..tip_wide.len()].copy_from_slice(&tip_wide);
+    nid.szTip[tip_wide.len()] = 0;
+}
+
+/// Shows a balloon notification from the tray icon
+pub fn show_balloon(title: &str, text: &str, flags: NOTIFY_ICON_INFOTIP_FLAGS) {
+    let hwnd = unsafe { TRAY_HWND };
+    if hwnd.is_invalid() || !ICON_ADDED.load(Ordering::SeqCst) {
+        return;
+    }
+    let mut nid: NOTIFYICONDATAW = unsafe { std::mem::zeroed() };
+    nid.cbSize = std::mem::size_of::<NOTIFYICONDATAW>() as u32;
+    nid.hWnd = hwnd;
+    nid.uID = 1;
+    nid.uFlags = NIF_INFO;
+    nid.dwInfoFlags = flags;
+    let title: Vec<u16> = title
+        .encode_utf16()
+        .take(nid.szInfoTitle.len() - 1)
+        .collect();
+    nid.szInfoTitle[..title.len()].copy_from_slice(&title);
+    let text: Vec<u16> = text.encode_utf16().take(nid.szInfo.len() - 1).collect();
+    nid.szInfo[..text.len()].copy_from_slice(&text);
+    let _ = unsafe { Shell_NotifyIconW(NIM_MODIFY, &nid) };
+}
+
+/// Item data of an owner-drawn item. Screen readers take the name from the MSAA header.
+#[repr(C)]
+struct ItemData {
+    msaa: MSAAMENUINFO,
+    id: u32,
+    text: Vec<u16>, // backs `msaa.pszWText`
+}
+
+/// The menu being shown, for drawing its items and matching access keys
+#[allow(clippy::vec_box)] // item data must not move while Windows holds pointers to it
+struct ShownMenu {
+    menu: Menu,
+    _data: Vec<Box<ItemData>>,
+    positions: Vec<(isize, Vec<u32>)>, // popup menu handle, item ids in order
+}
+
+// Only used from the UI thread
+unsafe impl Send for ShownMenu {}
+
+// Delays a single click until the double-click time has passed
+const CLICK_TIMER_ID: usize = 6;
+
+// Set by a double click so the release that follows it is not a single click
+static SKIP_CLICK: AtomicBool = AtomicBool::new(false);
+
+static CURRENT_MENU: Mutex<Option<ShownMenu>> = Mutex::new(None);
+
+// lParam of the tray callback when the icon is chosen with Enter or Space
+const NIN_KEYSELECT: u32 = NIN_SELECT | 1;
+
+fn menu_state(config: &Config) -> MenuState {
+    let mut off: Vec<String> = config.saved_modes.keys().cloned().collect();
+    off.sort();
+    let primary = topology::primary();
+    MenuState {
+        monitors: topology::monitors()
+            .into_iter()
+            .map(|(m, friendly_name)| {
+                let name = monitor_name(config, &m, &friendly_name);
+                (m, name)
+            })
+            .collect(),
+        details: topology::details()
+            .iter()
+            .map(|(m, details)| {
+                let description = details.describe(Some(m) == primary.as_ref());
+                (m.clone(), description)
+            })
+            .collect(),
+        primary,
+        selected: config.secondary_monitors.clone(),
+        off: off
+            .into_iter()
+            .map(|m| {
+                let label = monitor_label(config, &m);
+                (m, label)
+            })
+            .collect(),
+        config_error: CONFIG_ERROR.lock().unwrap().clone(),
+        failed_hotkeys: FAILED_HOTKEYS.lock().unwrap().clone(),
+        next_event: scheduler::next_event(),
+        timer_status: timer::remaining_minutes(config)
+            .map(|minutes| tr("tooltip-back-on", &[("minutes", &minutes)])),
+        timer_presets: timer::PRESETS.to_vec(),
+        startup_enabled: is_startup_enabled(),
+    }
 }
 
 const WM_MEASUREITEM: u32 = 0x002C;
 const WM_DRAWITEM: u32 = 0x002B;
 
+// Read once per menu opening rather than on every paint
+static STARTUP_ENABLED: Mutex<Option<bool>> = Mutex::new(None);
+
 fn is_startup_enabled() -> bool {
+    *STARTUP_ENABLED
+        .lock()
+        .unwrap()
+        .get_or_insert_with(read_startup_enabled)
+}
+
+fn read_startup_enabled() -> bool {
     unsafe {
         let subkey: Vec<u16> = "Software\\Microsoft\\Windows\\CurrentVersion\\Run"
             .encode_utf16()
@@ -101,7 +347,9 @@ fn is_startup_enabled() -> bool {
 }
 
 fn toggle_startup() {
-    if is_startup_enabled() {
+    let enabled = read_startup_enabled();
+    *STARTUP_ENABLED.lock().unwrap() = Some(!enabled);
+    if enabled {
         // Remove from startup
         unsafe {
             let subkey: Vec<u16> = "Software\\Microsoft\\Windows\\CurrentVersion\\Run"
@@ -172,6 +420,19 @@ fn toggle_startup() {
     }
 }
 
+/// Runs the action configured for a click on the tray icon
+fn run_click(hwnd: HWND, action: &ClickAction) {
+    let config = unsafe { &mut *CONFIG };
+    match action {
+        ClickAction::Tray(TrayAction::None) => {}
+        ClickAction::Tray(TrayAction::Menu) => show_menu(hwnd, config),
+        ClickAction::Tray(TrayAction::RestoreAll) => restore_all(config),
+        ClickAction::Action(action) => {
+            confirm::apply(config, |config| actions::run(action, config))
+        }
+    }
+}
+
 pub unsafe extern "system" fn window_proc(
     hwnd: HWND,
     msg: u32,
@@ -180,92 +441,170 @@ pub unsafe extern "system" fn window_proc(
 ) -> LRESULT {
     match msg {
         WM_USER => {
-            if lparam.0 as u32 == WM_LBUTTONDBLCLK {
-                // double left click, toggle
+            let event = lparam.0 as u32;
+            if unsafe { CONFIG.is_null() } {
+                return LRESULT(0);
+            }
+            let clicks = unsafe { (*CONFIG).clicks.clone() };
+            if event == WM_LBUTTONUP {
+                if unsafe { GetKeyState(VK_SHIFT.0 as i32) } < 0 {
+                    run_click(hwnd, &clicks.shift);
+                } else if SKIP_CLICK.swap(false, Ordering::SeqCst) {
+                    // release ending a double click
+                } else if clicks.double.is_none() {
+                    run_click(hwnd, &clicks.single);
+                } else if !clicks.single.is_none() {
+                    // wait to see whether this becomes a double click
+                    let wait = unsafe { GetDoubleClickTime() };
+                    unsafe { SetTimer(Some(hwnd), CLICK_TIMER_ID, wait, None) };
+                }
+            } else if event == WM_LBUTTONDBLCLK {
+                let _ = unsafe { KillTimer(Some(hwnd), CLICK_TIMER_ID) };
+                SKIP_CLICK.store(true, Ordering::SeqCst);
+                run_click(hwnd, &clicks.double);
+            } else if event == WM_MBUTTONUP {
+                run_click(hwnd, &clicks.middle);
+            } else if event == WM_CONTEXTMENU || event == NIN_KEYSELECT {
+                // right click, Shift+F10 or Enter on the icon, show menu
                 unsafe {
                     if !CONFIG.is_null() {
-                        toggle_monitors(&mut *CONFIG);
+                        show_menu(hwnd, &mut *CONFIG);
                     }
                 }
-            } else if lparam.0 as u32 == WM_RBUTTONUP {
-                // right click, show menu
+            } else if event == NIN_BALLOONUSERCLICK {
+                // clicked notification, undo its change
                 unsafe {
                     if !CONFIG.is_null() {
-                        show_menu(hwnd, &mut *CONFIG);
+                        confirm::apply(&mut *CONFIG, notify::undo);
                     }
                 }
             }
         }
+        WM_HOTKEY if wparam.0 as i32 == recovery::RESTORE_HOTKEY_ID => unsafe {
+            if !CONFIG.is_null() {
+                eprintln!("Restore hotkey pressed, turning all monitors back on");
+                restore_all(&mut *CONFIG);
+            }
+        },
         WM_HOTKEY => {
-            // Global hotkey pressed, toggle monitors
-            unsafe {
-                if !CONFIG.is_null() {
-                    toggle_monitors(&mut *CONFIG);
+            // Global hotkey pressed, run its action once the binding is complete
+            if let Some(action) = hotkeys::handle_hotkey(hwnd, lparam) {
+                unsafe {
+                    if !CONFIG.is_null() {
+                        confirm::apply(&mut *CONFIG, |config| actions::run(&action, config));
+                    }
+                }
+            }
+        }
+        WM_TIMER if wparam.0 == timer::TIMER_ID => unsafe {
+            if !CONFIG.is_null() {
+                timer::tick(&mut *CONFIG);
+            }
+        },
+        WM_TIMER if wparam.0 == idle::TIMER_ID => unsafe {
+            if !CONFIG.is_null() {
+                idle::tick(&mut *CONFIG);
+            }
+        },
+        WM_TIMER if wparam.0 == scheduler::TIMER_ID => unsafe {
+            if !CONFIG.is_null() {
+                scheduler::tick(&mut *CONFIG);
+            }
+        },
+        WM_TIMER if wparam.0 == app_rules::TIMER_ID => unsafe {
+            if !CONFIG.is_null() {
+                app_rules::evaluate(&mut *CONFIG);
+            }
+        },
+        WM_TIMER if wparam.0 == CLICK_TIMER_ID => {
+            // single click that did not become a double click
+            let _ = unsafe { KillTimer(Some(hwnd), CLICK_TIMER_ID) };
+            if unsafe { !CONFIG.is_null() } {
+                let single = unsafe { (*CONFIG).clicks.single.clone() };
+                run_click(hwnd, &single);
+            }
+        }
+        WM_TIMER if wparam.0 == hotkeys::SEQUENCE_TIMER_ID => {
+            if let Some(action) = hotkeys::handle_timeout(hwnd) {
+                unsafe {
+                    if !CONFIG.is_null() {
+                        confirm::apply(&mut *CONFIG, |config| actions::run(&action, config));
+                    }
+                }
+            }
+        }
+        WM_POWERBROADCAST => {
+            if wparam.0 as u32 == PBT_APMPOWERSTATUSCHANGE {
+                unsafe {
+                    if !CONFIG.is_null() {
+                        power::handle_power_change(&mut *CONFIG);
+                    }
+                }
+            }
+            return LRESULT(1);
+        }
+        WM_WTSSESSION_CHANGE => unsafe {
+            if !CONFIG.is_null() {
+                session::handle_session_change(&mut *CONFIG, wparam.0 as u32);
+            }
+        },
+        hooks::WM_HOOK_DONE => unsafe {
+            if !CONFIG.is_null() {
+                monitors::hook_done(&mut *CONFIG, wparam.0, lparam.0 != 0);
+            }
+        },
+        WM_COPYDATA => {
+            // Action sent from the command line
+            let copy_data = unsafe { &*(lparam.0 as *const COPYDATASTRUCT) };
+            if copy_data.dwData != COPYDATA_ACTION || CONFIG.is_null() {
+                return LRESULT(0);
+            }
+            let data = unsafe {
+                std::slice::from_raw_parts(copy_data.lpData as *const u8, copy_data.cbData as usize)
+            };
+            match serde_json::from_slice::<Action>(data) {
+                Ok(action) => {
+                    unsafe { confirm::apply(&mut *CONFIG, |config| actions::run(&action, config)) };
+                    return LRESULT(1);
+                }
+                Err(e) => {
+                    eprintln!("Invalid command line action: {}", e);
+                    return LRESULT(0);
                 }
             }
         }
         WM_MEASUREITEM => {
             let measure_item = unsafe { &mut *(lparam.0 as *mut MEASUREITEMSTRUCT) };
             if measure_item.CtlType == ODT_MENU {
-                measure_item.itemWidth = 250;
-                measure_item.itemHeight = 30;
+                let theme = theme::current();
+                measure_item.itemWidth = theme.scale(250) as u32;
+                measure_item.itemHeight = theme.scale(30) as u32;
                 return LRESULT(1);
             }
         }
         WM_DRAWITEM => {
             let draw_item = unsafe { &mut *(lparam.0 as *mut DRAWITEMSTRUCT) };
             if draw_item.CtlType == ODT_MENU {
-                let item_id = draw_item.itemID;
-                let all_monitors = get_monitors();
-                let screens_off = unsafe { !(*CONFIG).saved_modes.is_empty() };
-
-                let (text, checked, disabled, is_separator) = if screens_off {
-                    // Screen off mode menu
-                    if item_id == 1000 {
-                        ("Turn back on", false, false, false)
-                    } else if item_id == 1001 {
-                        ("", false, false, true)
-                    } else if item_id == 1002 {
-                        ("Start on Sign in", is_startup_enabled(), false, false)
-                    } else if item_id == 1003 {
-                        ("", false, false, true)
-                    } else if item_id == 1004 {
-                        ("Exit", false, false, false)
-                    } else {
-                        ("", false, false, false)
-                    }
-                } else {
-                    // Screen on mode menu
-                    if item_id == 0 {
-                        ("Select Monitors to turn off:", false, true, false)
-                    } else if item_id <= all_monitors.len() as u32 {
-                        let index = (item_id - 1) as usize;
-                        let (monitor, friendly_name) = &all_monitors[index];
-                        let checked = unsafe { (*CONFIG).secondary_monitors.contains(monitor) };
-                        (friendly_name.as_str(), checked, false, false)
-                    } else if item_id == all_monitors.len() as u32 + 1 {
-                        ("", false, false, true)
-                    } else if item_id == all_monitors.len() as u32 + 2 {
-                        ("Turn off selected screens", false, false, false)
-                    } else if item_id == all_monitors.len() as u32 + 3 {
-                        ("", false, false, true)
-                    } else if item_id == all_monitors.len() as u32 + 4 {
-                        ("Start on Sign in", is_startup_enabled(), false, false)
-                    } else if item_id == all_monitors.len() as u32 + 5 {
-                        ("Exit", false, false, false)
-                    } else {
-                        ("", false, false, false)
-                    }
+                let data = draw_item.itemData as *const ItemData;
+                if data.is_null() {
+                    return LRESULT(1);
+                }
+                let id = unsafe { (*data).id };
+                let shown = CURRENT_MENU.lock().unwrap();
+                let Some(item) = shown.as_ref().and_then(|shown| shown.menu.find(id)) else {
+                    return LRESULT(1);
                 };
+                let (text, checked, disabled) = (item.label.as_str(), item.checked, item.disabled);
+                let is_separator = item.kind == ItemKind::Separator;
+                let theme = theme::current();
                 if is_separator {
                     // draw separator
                     let rect = &draw_item.rcItem;
                     let hdc = draw_item.hDC;
-                    let bg_color = COLORREF(0x00222222);
-                    let brush = unsafe { CreateSolidBrush(bg_color) };
+                    let brush = unsafe { CreateSolidBrush(theme.background) };
                     unsafe { FillRect(hdc, rect, brush) };
                     let _ = unsafe { DeleteObject(HGDIOBJ(brush.0)) };
-                    let pen = unsafe { CreatePen(PS_SOLID, 1, COLORREF(0x00444444)) };
+                    let pen = unsafe { CreatePen(PS_SOLID, theme.scale(1), theme.separator) };
                     let old_pen = unsafe { SelectObject(hdc, HGDIOBJ(pen.0)) };
                     let _ = unsafe {
                         MoveToEx(
@@ -283,15 +622,18 @@ pub unsafe extern "system" fn window_proc(
                 }
                 let rect = &draw_item.rcItem;
                 let hdc = draw_item.hDC;
-                let bg_color = if (draw_item.itemState.0 & ODS_SELECTED.0) != 0 {
-                    COLORREF(0x00333333)
+                let selected = (draw_item.itemState.0 & ODS_SELECTED.0) != 0;
+                let bg_color = if selected {
+                    theme.highlight
                 } else {
-                    COLORREF(0x00222222)
+                    theme.background
                 };
                 let text_color = if disabled {
-                    COLORREF(0x00888888)
+                    theme.disabled_text
+                } else if selected {
+                    theme.highlight_text
                 } else {
-                    COLORREF(0x00FFFFFF)
+                    theme.text
                 };
                 let brush = unsafe { CreateSolidBrush(bg_color) };
                 unsafe { FillRect(hdc, rect, brush) };
@@ -307,23 +649,42 @@ pub unsafe extern "system" fn window_proc(
                     .chain(std::iter::once(0))
                     .collect();
                 let draw_rect = RECT {
-                    left: rect.left + if checked { 8 } else { 24 },
-                    top: rect.top + 4,
-                    right: rect.right - 8,
-                    bottom: rect.bottom - 4,
+                    left: rect.left + theme.scale(if checked { 8 } else { 24 }),
+                    top: rect.top + theme.scale(4),
+                    right: rect.right - theme.scale(8),
+                    bottom: rect.bottom - theme.scale(4),
                 };
                 let mut rect_copy = draw_rect;
-                unsafe {
-                    DrawTextW(
-                        hdc,
-                        &mut text_wide,
-                        &mut rect_copy as *mut _,
-                        DT_LEFT | DT_SINGLELINE | DT_VCENTER,
-                    )
-                };
+                // Access keys are underlined once the menu is used with the keyboard
+                let mut prefix_flags = DT_LEFT | DT_SINGLELINE | DT_VCENTER;
+                if (draw_item.itemState.0 & ODS_NOACCEL.0) != 0 {
+                    prefix_flags |= DT_HIDEPREFIX;
+                }
+                unsafe { DrawTextW(hdc, &mut text_wide, &mut rect_copy as *mut _, prefix_flags) };
                 return LRESULT(1);
             }
         }
+        WM_MENUCHAR => {
+            // Access keys of owner-drawn items are matched here rather than by Windows
+            let key = char::from_u32(wparam.0 as u32 & 0xFFFF);
+            if let Some(position) = key.and_then(|key| menu_char(lparam.0, key)) {
+                return LRESULT(((MNC_EXECUTE << 16) | position as u32) as isize);
+            }
+            return LRESULT(0);
+        }
+        WM_DISPLAYCHANGE => unsafe {
+            // The layout may have changed outside the app
+            topology::invalidate();
+            if !CONFIG.is_null() {
+                update_tray_icon(&*CONFIG);
+            }
+        },
+        WM_ENDSESSION => unsafe {
+            // The process can be ended any time after this returns
+            if wparam.0 != 0 && !CONFIG.is_null() {
+                recovery::on_exit(&mut *CONFIG, recovery::ExitReason::SessionEnd);
+            }
+        },
         WM_DESTROY => {
             PostQuitMessage(0);
         }
@@ -332,69 +693,89 @@ pub unsafe extern "system" fn window_proc(
     LRESULT(0)
 }
 
-pub fn show_menu(hwnd: HWND, config: &mut Config) {
-    unsafe {
-        let hmenu = CreatePopupMenu().unwrap();
-        let all_monitors = get_monitors();
-        let screens_off = !config.saved_modes.is_empty();
-
-        if screens_off {
-            // Screen off mode: Show "Turn back on" and "Exit" only
-            // "Turn back on"
-            let _ = AppendMenuW(hmenu, MF_OWNERDRAW, 1000 as usize, PCWSTR::null());
-
-            // Separator
-            let _ = AppendMenuW(hmenu, MF_OWNERDRAW, 1001 as usize, PCWSTR::null());
-
-            // "Start on Sign in"
-            let _ = AppendMenuW(hmenu, MF_OWNERDRAW, 1002 as usize, PCWSTR::null());
-
-            // Separator
-            let _ = AppendMenuW(hmenu, MF_OWNERDRAW, 1003 as usize, PCWSTR::null());
-
-            // Exit
-            let _ = AppendMenuW(hmenu, MF_OWNERDRAW, 1004 as usize, PCWSTR::null());
-        } else {
-            // Screen on mode: Show full menu
-            // Header
-            let id = 0u32;
-            let _ = AppendMenuW(hmenu, MF_OWNERDRAW, id as usize, PCWSTR::null());
-
-            // Monitors
-            for (i, _) in all_monitors.iter().enumerate() {
-                let id = (i + 1) as u32;
-                let _ = AppendMenuW(hmenu, MF_OWNERDRAW, id as usize, PCWSTR::null());
+#[allow(clippy::vec_box)]
+fn append_items(
+    hmenu: HMENU,
+    items: &[Item],
+    data: &mut Vec<Box<ItemData>>,
+    positions: &mut Vec<(isize, Vec<u32>)>,
+) {
+    positions.push((hmenu.0 as isize, items.iter().map(|item| item.id).collect()));
+    for item in items {
+        let text: Vec<u16> = item
+            .label
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let mut item_data = Box::new(ItemData {
+            msaa: MSAAMENUINFO {
+                dwMSAASignature: MSAA_MENU_SIG as u32,
+                cchWText: text.len() as u32 - 1,
+                pszWText: PWSTR::null(),
+            },
+            id: item.id,
+            text,
+        });
+        item_data.msaa.pszWText = PWSTR(item_data.text.as_mut_ptr());
+
+        let mut flags = MF_OWNERDRAW;
+        if item.checked {
+            flags |= MF_CHECKED;
+        }
+        if item.disabled {
+            flags |= MF_DISABLED | MF_GRAYED;
+        }
+        // Item data is handed back in WM_DRAWITEM
+        let pointer = PCWSTR(&*item_data as *const ItemData as *const u16);
+        unsafe {
+            if let ItemKind::Submenu(children) = &item.kind {
+                let submenu = CreatePopupMenu().unwrap();
+                append_items(submenu, children, data, positions);
+                let _ = AppendMenuW(hmenu, flags | MF_POPUP, submenu.0 as usize, pointer);
+            } else {
+                let _ = AppendMenuW(hmenu, flags, item.id as usize, pointer);
             }
-
-            // Separator
-            let id = (all_monitors.len() + 1) as u32;
-            let _ = AppendMenuW(hmenu, MF_OWNERDRAW, id as usize, PCWSTR::null());
-
-            // "Turn off selected screens"
-            let id = (all_monitors.len() + 2) as u32;
-            let _ = AppendMenuW(hmenu, MF_OWNERDRAW, id as usize, PCWSTR::null());
-
-            // Separator
-            let id = (all_monitors.len() + 3) as u32;
-            let _ = AppendMenuW(hmenu, MF_OWNERDRAW, id as usize, PCWSTR::null());
-
-            // "Start on Sign in"
-            let id = (all_monitors.len() + 4) as u32;
-            let _ = AppendMenuW(hmenu, MF_OWNERDRAW, id as usize, PCWSTR::null());
-
-            // Exit
-            let id = (all_monitors.len() + 5) as u32;
-            let _ = AppendMenuW(hmenu, MF_OWNERDRAW, id as usize, PCWSTR::null());
         }
+        data.push(item_data);
+    }
+}
 
-        let mut pt: POINT = std::mem::zeroed();
-        GetCursorPos(&mut pt).unwrap();
-
-        // Required for tray menus: set foreground window so menu dismisses when clicking outside
-        let _ = SetForegroundWindow(hwnd);
+/// Position of the item in a popup menu whose access key is `key`
+fn menu_char(hmenu: isize, key: char) -> Option<usize> {
+    let shown = CURRENT_MENU.lock().unwrap();
+    let shown = shown.as_ref()?;
+    let (_, ids) = shown.positions.iter().find(|(h, _)| *h == hmenu)?;
+    ids.iter().position(|id| {
+        shown.menu.find(*id).is_some_and(|item| {
+            !item.disabled
+                && mnemonic(&item.label).is_some_and(|m| m.to_lowercase().eq(key.to_lowercase()))
+        })
+    })
+}
 
-        loop {
-            let cmd = TrackPopupMenu(
+pub fn show_menu(hwnd: HWND, config: &mut Config) {
+    let mut pt = POINT::default();
+    unsafe { GetCursorPos(&mut pt).unwrap() };
+    theme::refresh(&config.theme, pt);
+
+    // Required for tray menus: set foreground window so menu dismisses when clicking outside
+    let _ = unsafe { SetForegroundWindow(hwnd) };
+    *STARTUP_ENABLED.lock().unwrap() = None;
+
+    loop {
+        let menu = Menu::build(&menu_state(config));
+        let hmenu = unsafe { CreatePopupMenu() }.unwrap();
+        let mut data = Vec::new();
+        let mut positions = Vec::new();
+        append_items(hmenu, &menu.items, &mut data, &mut positions);
+        *CURRENT_MENU.lock().unwrap() = Some(ShownMenu {
+            menu,
+            _data: data,
+            positions,
+        });
+
+        let cmd = unsafe {
+            TrackPopupMenu(
                 hmenu,
                 TPM_RETURNCMD | TPM_NONOTIFY,
                 pt.x,
@@ -402,66 +783,55 @@ pub fn show_menu(hwnd: HWND, config: &mut Config) {
                 Some(0),
                 hwnd,
                 None,
-            );
+            )
+        };
 
-            // Required for tray menus: post a message to ensure menu closes properly
-            let _ = PostMessageW(Some(hwnd), WM_NULL, WPARAM(0), LPARAM(0));
-
-            if cmd.0 > 0 {
-                if screens_off {
-                    // Screen off mode menu
-                    if cmd.0 == 1000 {
-                        // "Turn back on"
-                        toggle_monitors(config);
-                        break;
-                    } else if cmd.0 == 1002 {
-                        // "Start on Sign in"
-                        toggle_startup();
-                        // Continue loop to re-show menu
-                    } else if cmd.0 == 1004 {
-                        // Exit
-                        PostQuitMessage(0);
-                        break;
-                    } else {
-                        break;
-                    }
-                } else {
-                    // Screen on mode menu
-                    let index = (cmd.0 - 1) as usize;
-                    if index < all_monitors.len() {
-                        if let Some((monitor, _)) = all_monitors.get(index) {
-                            if config.secondary_monitors.contains(monitor) {
-                                config.secondary_monitors.retain(|m| m != monitor);
-                                save_config(config);
-                            } else {
-                                // Prevent marking all monitors as secondary
-                                if config.secondary_monitors.len() + 1 < all_monitors.len() {
-                                    config.secondary_monitors.push(monitor.clone());
-                                    save_config(config);
-                                }
-                            }
-                        }
-                        // Continue the loop to re-show the menu
-                    } else if index == all_monitors.len() + 1 {
-                        // "Turn off selected screens"
-                        toggle_monitors(config);
-                        break;
-                    } else if index == all_monitors.len() + 3 {
-                        // "Start on Sign in"
-                        toggle_startup();
-                        // Continue loop to re-show menu
-                    } else if index == all_monitors.len() + 4 {
-                        // Exit
-                        PostQuitMessage(0);
-                        break;
-                    } else {
-                        break;
-                    }
-                }
-            } else {
-                break;
+        // Required for tray menus: post a message to ensure menu closes properly
+        let _ = unsafe { PostMessageW(Some(hwnd), WM_NULL, WPARAM(0), LPARAM(0)) };
+        let _ = unsafe { DestroyMenu(hmenu) };
+
+        let command = CURRENT_MENU
+            .lock()
+            .unwrap()
+            .take()
+            .and_then(|shown| shown.menu.command(cmd.0 as u32).cloned());
+        let Some(command) = command else {
+            break;
+        };
+        run_command(config, &command);
+        if !command.keeps_menu_open() {
+            break;
+        }
+    }
+}
+
+fn run_command(config: &mut Config, command: &MenuCommand) {
+    match command {
+        MenuCommand::SelectMonitor(monitor) => {
+            if config.secondary_monitors.contains(monitor) {
+                config.secondary_monitors.retain(|m| m != monitor);
+                save_config(config);
+            } else if config.secondary_monitors.len() + 1 < topology::monitors().len() {
+                // Prevent marking all monitors as secondary
+                config.secondary_monitors.push(monitor.clone());
+                save_config(config);
             }
         }
-        let _ = DestroyMenu(hmenu);
+        MenuCommand::TurnOffSelected | MenuCommand::TurnBackOn => {
+            confirm::apply(config, toggle_monitors)
+        }
+        MenuCommand::TimedOff(minutes) => {
+            confirm::apply(config, |config| timer::start(config, *minutes))
+        }
+        MenuCommand::MonitorOn(monitor) => confirm::apply(config, |config| {
+            turn_on(config, std::slice::from_ref(monitor))
+        }),
+        MenuCommand::MonitorOff(monitor) => confirm::apply(config, |config| {
+            turn_off(config, std::slice::from_ref(monitor))
+        }),
+        MenuCommand::Rename(monitor) => rename::open(config, monitor),
+        MenuCommand::Identify => identify::show(config),
+        MenuCommand::ToggleStartup => toggle_startup(),
+        MenuCommand::Exit => unsafe { PostQuitMessage(0) },
     }
 }